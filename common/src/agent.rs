@@ -0,0 +1,24 @@
+// A game tree seen from the agents, its information sets are numbered from 0
+pub trait InfoSets {
+    fn n_info_sets(&self) -> usize;
+}
+
+// Anything that plays the game: a trained or saved profile or one of the baseline bots
+pub trait Agent<T: InfoSets, const N: usize> {
+    // Strategy at the information set `info_set` of `tree`
+    fn strategy(&self, tree: &T, info_set: usize) -> [f64; N];
+
+    // Strategy at every information set, in the order of the tree
+    fn profile(&self, tree: &T) -> Vec<[f64; N]> {
+        (0..tree.n_info_sets())
+            .map(|info_set| self.strategy(tree, info_set))
+            .collect()
+    }
+}
+
+// Profiles are indexed like the information sets of the tree they come from
+impl<T: InfoSets, const N: usize> Agent<T, N> for Vec<[f64; N]> {
+    fn strategy(&self, _: &T, info_set: usize) -> [f64; N] {
+        self[info_set]
+    }
+}
@@ -0,0 +1,10 @@
+// The parts of the solvers that do not depend on the game, shared by the Kuhn
+// (task2) and Leduc (task3) crates
+
+pub mod agent;
+pub mod minimizer;
+pub mod pruning;
+pub mod purification;
+pub mod simplex;
+pub mod simulate;
+pub mod tournament;
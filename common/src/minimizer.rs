@@ -0,0 +1,204 @@
+use std::fmt;
+use std::str::FromStr;
+
+// How an information set turns its regrets into the next strategy, e.g. `--minimizer rm+`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RegretMinimizer {
+    // Play in proportion to the positive cumulative regrets
+    #[default]
+    RegretMatching,
+    // Regret matching with the cumulative regrets floored at zero after every iteration
+    RegretMatchingPlus,
+    // Regret matching+ that also counts the regrets of the last iteration, as a
+    // prediction of the next ones
+    PredictiveRegretMatchingPlus,
+    // Multiplicative weights, play in proportion to exp(learning rate * cumulative regret)
+    Hedge(f64),
+}
+
+impl RegretMinimizer {
+    // Add the regrets of the last iteration to the cumulative regrets
+    pub fn accumulate(&self, regret_sum: &mut [f64], regret: &[f64]) {
+        let floored = matches!(
+            self,
+            RegretMinimizer::RegretMatchingPlus | RegretMinimizer::PredictiveRegretMatchingPlus
+        );
+
+        for (a, &b) in regret_sum.iter_mut().zip(regret.iter()) {
+            *a += b;
+            if floored {
+                *a = a.max(0.0);
+            }
+        }
+    }
+
+    // Next strategy over the legal actions from the cumulative regrets and the regrets
+    // of the last iteration
+    pub fn strategy<const N: usize>(
+        &self,
+        regret_sum: &[f64],
+        regret: &[f64],
+        legal: &[bool; N],
+    ) -> [f64; N] {
+        let mut strategy = [0.0; N];
+
+        match *self {
+            RegretMinimizer::RegretMatching | RegretMinimizer::RegretMatchingPlus => {
+                strategy.copy_from_slice(regret_sum);
+                strategy = strategy.map(|x| x.max(0.0));
+            }
+            RegretMinimizer::PredictiveRegretMatchingPlus => {
+                strategy
+                    .iter_mut()
+                    .zip(regret_sum.iter().zip(regret.iter()))
+                    .for_each(|(x, (&a, &b))| *x = (a + b).max(0.0));
+            }
+            RegretMinimizer::Hedge(learning_rate) => {
+                // Shifting by the largest regret keeps the exponentials finite
+                let max = regret_sum
+                    .iter()
+                    .zip(legal.iter())
+                    .filter(|(_, &is_legal)| is_legal)
+                    .map(|(&a, _)| a)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                strategy
+                    .iter_mut()
+                    .zip(regret_sum.iter())
+                    .for_each(|(x, &a)| *x = (learning_rate * (a - max)).exp());
+            }
+        }
+        mask_illegal(&mut strategy, legal);
+
+        let total = strategy.iter().sum::<f64>();
+
+        if total > 0.0 {
+            strategy.map(|x| x / total)
+        } else {
+            uniform_strategy(legal)
+        }
+    }
+}
+
+// Spread the probability evenly over the legal actions
+pub fn uniform_strategy<const N: usize>(legal: &[bool; N]) -> [f64; N] {
+    let n_legal = legal.iter().filter(|&&is_legal| is_legal).count();
+
+    legal.map(|is_legal| if is_legal { 1.0 / n_legal as f64 } else { 0.0 })
+}
+
+pub fn mask_illegal<const N: usize>(strategy: &mut [f64; N], legal: &[bool; N]) {
+    strategy
+        .iter_mut()
+        .zip(legal.iter())
+        .filter(|(_, &is_legal)| !is_legal)
+        .for_each(|(x, _)| *x = 0.0);
+}
+
+impl fmt::Display for RegretMinimizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegretMinimizer::RegretMatching => write!(f, "rm"),
+            RegretMinimizer::RegretMatchingPlus => write!(f, "rm+"),
+            RegretMinimizer::PredictiveRegretMatchingPlus => write!(f, "prm+"),
+            RegretMinimizer::Hedge(learning_rate) => write!(f, "hedge:{}", learning_rate),
+        }
+    }
+}
+
+impl FromStr for RegretMinimizer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RegretMinimizer, String> {
+        let error = || {
+            format!(
+                "unknown regret minimizer {}, expected rm, rm+, prm+ or hedge:RATE",
+                s
+            )
+        };
+
+        match s.split_once(':') {
+            None => match s {
+                "rm" => Ok(RegretMinimizer::RegretMatching),
+                "rm+" => Ok(RegretMinimizer::RegretMatchingPlus),
+                "prm+" => Ok(RegretMinimizer::PredictiveRegretMatchingPlus),
+                "hedge" => Ok(RegretMinimizer::Hedge(1.0)),
+                _ => Err(error()),
+            },
+            Some(("hedge", value)) => value
+                .parse()
+                .ok()
+                .filter(|&learning_rate: &f64| learning_rate > 0.0)
+                .map(RegretMinimizer::Hedge)
+                .ok_or_else(error),
+            Some(_) => Err(error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_minimizer() {
+        for name in ["rm", "rm+", "prm+", "hedge:0.5"] {
+            assert_eq!(name.parse::<RegretMinimizer>().unwrap().to_string(), name);
+        }
+        assert!("hedge:0".parse::<RegretMinimizer>().is_err());
+        assert!("cfr".parse::<RegretMinimizer>().is_err());
+    }
+
+    #[test]
+    fn plus_variants_floor_the_regrets() {
+        let mut plain = [1.0, -1.0, 2.0];
+        let mut floored = [1.0, -1.0, 2.0];
+        RegretMinimizer::RegretMatching.accumulate(&mut plain, &[-2.0, -1.0, 1.0]);
+        RegretMinimizer::RegretMatchingPlus.accumulate(&mut floored, &[-2.0, -1.0, 1.0]);
+
+        assert_eq!(plain, [-1.0, -2.0, 3.0]);
+        assert_eq!(floored, [0.0, 0.0, 3.0]);
+    }
+
+    #[test]
+    fn strategies_follow_the_regrets() {
+        let regret_sum = [3.0, 1.0];
+        let regret = [-3.0, 1.0];
+        let legal = [true; 2];
+
+        assert_eq!(
+            RegretMinimizer::RegretMatching.strategy(&regret_sum, &regret, &legal),
+            [0.75, 0.25]
+        );
+        assert_eq!(
+            RegretMinimizer::PredictiveRegretMatchingPlus.strategy(&regret_sum, &regret, &legal),
+            [0.0, 1.0]
+        );
+
+        let hedge = RegretMinimizer::Hedge(1.0).strategy(&regret_sum, &regret, &legal);
+        assert!((hedge[0] / hedge[1] - 2f64.exp()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn strategies_skip_illegal_actions() {
+        let regret_sum = [3.0, 1.0, 5.0];
+        let regret = [-3.0, 1.0, 5.0];
+        let legal = [true, true, false];
+
+        assert_eq!(
+            RegretMinimizer::RegretMatching.strategy(&regret_sum, &regret, &legal),
+            [0.75, 0.25, 0.0]
+        );
+        assert_eq!(
+            RegretMinimizer::PredictiveRegretMatchingPlus.strategy(&regret_sum, &regret, &legal),
+            [0.0, 1.0, 0.0]
+        );
+
+        let hedge = RegretMinimizer::Hedge(1.0).strategy(&regret_sum, &regret, &legal);
+        assert_eq!(hedge[2], 0.0);
+        assert!((hedge[0] / hedge[1] - 2f64.exp()).abs() < 1e-9);
+        assert_eq!(
+            RegretMinimizer::RegretMatching.strategy(&[-1.0; 3], &regret, &legal),
+            [0.5, 0.5, 0.0]
+        );
+    }
+}
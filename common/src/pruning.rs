@@ -0,0 +1,108 @@
+use crate::minimizer::RegretMinimizer;
+
+// Regret-based pruning, e.g. `--prune 10 --prune-threshold -1`
+//
+// CFR skips the subtree of an action that is not played and whose cumulative regret
+// is below `threshold`. Every `interval` iterations the whole tree is traversed.
+//
+// The regrets of the skipped iterations are never computed, unlike the regret-based
+// pruning of Brown and Sandholm that bounds them with a best response. An action
+// that is visited again is assumed to have had the regret of that iteration on
+// every iteration it missed, see `catch_up`. This is exact only when the other
+// players did not change their strategies in the meantime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pruning {
+    pub threshold: f64,
+    pub interval: usize,
+}
+
+impl Default for Pruning {
+    fn default() -> Pruning {
+        Pruning {
+            threshold: -1.0,
+            interval: 10,
+        }
+    }
+}
+
+impl Pruning {
+    pub fn is_full_iteration(&self, iteration: usize) -> bool {
+        iteration.is_multiple_of(self.interval)
+    }
+
+    pub fn skips(&self, strategy_pr: f64, regret_sum: f64) -> bool {
+        strategy_pr == 0.0 && regret_sum < self.threshold
+    }
+
+    // Hedge plays every action and the plus variants floor the regrets at zero, so
+    // the pruning would never skip anything with them below a positive threshold
+    pub fn check(&self, minimizer: RegretMinimizer) -> Result<(), String> {
+        match minimizer {
+            RegretMinimizer::RegretMatching => Ok(()),
+            RegretMinimizer::Hedge(_) => Err(format!(
+                "--prune never skips an action with --minimizer {}, it plays every action",
+                minimizer
+            )),
+            _ if self.threshold <= 0.0 => Err(format!(
+                "--prune never skips an action with --minimizer {} and --prune-threshold {}, \
+                 the regrets are floored at zero",
+                minimizer, self.threshold
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Count the iterations every action of an information set has been pruned for, an
+// action that is traversed again has its `regret` scaled by the iterations it missed
+pub fn catch_up(regret: &mut [f64], n_pruned: &mut [usize], pruned: &[bool]) {
+    for ((regret, n_pruned), &is_pruned) in regret.iter_mut().zip(n_pruned).zip(pruned) {
+        if is_pruned {
+            *n_pruned += 1;
+        } else {
+            *regret *= (*n_pruned + 1) as f64;
+            *n_pruned = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returning_actions_repeat_their_regret() {
+        let mut n_pruned = [0, 0];
+
+        // The second action is pruned for two iterations
+        for _ in 0..2 {
+            let mut regret = [1.0, 0.0];
+            catch_up(&mut regret, &mut n_pruned, &[false, true]);
+            assert_eq!(regret, [1.0, 0.0]);
+        }
+        assert_eq!(n_pruned, [0, 2]);
+
+        // It stands for the two iterations it missed and the one it is back on
+        let mut regret = [1.0, -0.5];
+        catch_up(&mut regret, &mut n_pruned, &[false, false]);
+        assert_eq!(regret, [1.0, -1.5]);
+        assert_eq!(n_pruned, [0, 0]);
+    }
+
+    #[test]
+    fn only_minimizers_that_can_prune_are_accepted() {
+        let pruning = Pruning::default();
+        let positive = Pruning {
+            threshold: 0.5,
+            ..pruning
+        };
+
+        assert!(pruning.check(RegretMinimizer::RegretMatching).is_ok());
+        for minimizer in ["rm+", "prm+", "hedge"] {
+            let minimizer: RegretMinimizer = minimizer.parse().unwrap();
+            assert!(pruning.check(minimizer).is_err(), "{}", minimizer);
+        }
+        assert!(positive.check(RegretMinimizer::RegretMatchingPlus).is_ok());
+        assert!(positive.check(RegretMinimizer::Hedge(1.0)).is_err());
+    }
+}
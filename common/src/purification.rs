@@ -0,0 +1,146 @@
+use std::fmt;
+use std::str::FromStr;
+
+// How an average strategy is cleaned up before it is exported or played, e.g.
+// `--purify threshold:0.01`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purification {
+    None,
+    // Drop the actions played with a lower probability
+    Threshold(f64),
+    // Always play the most likely action
+    Argmax,
+    // Keep the k most likely actions
+    TopK(usize),
+}
+
+impl Default for Purification {
+    fn default() -> Purification {
+        Purification::Threshold(0.001)
+    }
+}
+
+impl Purification {
+    // The kept actions are renormalised, ties go to the first action
+    pub fn apply<const N: usize>(&self, strategy: [f64; N]) -> [f64; N] {
+        let mut order: Vec<usize> = (0..N).collect();
+        order.sort_by(|&a, &b| strategy[b].total_cmp(&strategy[a]));
+
+        let n_kept = match *self {
+            Purification::None => N,
+            Purification::Threshold(threshold) => {
+                strategy.iter().filter(|&&x| x >= threshold).count()
+            }
+            Purification::Argmax => 1,
+            Purification::TopK(k) => k,
+        };
+
+        let mut purified = [0.0; N];
+        for &action in order.iter().take(n_kept) {
+            purified[action] = strategy[action];
+        }
+
+        let total = purified.iter().sum::<f64>();
+
+        if total > 0.0 {
+            purified.map(|x| x / total)
+        } else {
+            strategy
+        }
+    }
+}
+
+impl fmt::Display for Purification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Purification::None => write!(f, "none"),
+            Purification::Threshold(threshold) => write!(f, "threshold:{}", threshold),
+            Purification::Argmax => write!(f, "argmax"),
+            Purification::TopK(k) => write!(f, "top:{}", k),
+        }
+    }
+}
+
+impl FromStr for Purification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Purification, String> {
+        let error = || {
+            format!(
+                "unknown purification {}, expected none, threshold:X, argmax or top:K",
+                s
+            )
+        };
+
+        match s.split_once(':') {
+            None => match s {
+                "none" => Ok(Purification::None),
+                "threshold" => Ok(Purification::default()),
+                "argmax" => Ok(Purification::Argmax),
+                _ => Err(error()),
+            },
+            Some(("threshold", value)) => value
+                .parse()
+                .ok()
+                .filter(|threshold: &f64| (0.0..=1.0).contains(threshold))
+                .map(Purification::Threshold)
+                .ok_or_else(error),
+            Some(("top", value)) => value
+                .parse()
+                .ok()
+                .filter(|&k| k > 0)
+                .map(Purification::TopK)
+                .ok_or_else(error),
+            Some(_) => Err(error()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_option() {
+        for name in ["none", "threshold:0.01", "argmax", "top:1"] {
+            assert_eq!(name.parse::<Purification>().unwrap().to_string(), name);
+        }
+        assert_eq!("threshold".parse(), Ok(Purification::Threshold(0.001)));
+        assert!("top:0".parse::<Purification>().is_err());
+        assert!("threshold:2".parse::<Purification>().is_err());
+        assert!("mean".parse::<Purification>().is_err());
+    }
+
+    #[test]
+    fn purified_strategies_are_distributions() {
+        let strategy = [0.0005, 0.9995];
+
+        assert_eq!(Purification::None.apply(strategy), strategy);
+        assert_eq!(Purification::default().apply(strategy), [0.0, 1.0]);
+        assert_eq!(Purification::Argmax.apply([0.6, 0.4]), [1.0, 0.0]);
+        assert_eq!(Purification::Argmax.apply([0.5, 0.5]), [1.0, 0.0]);
+        assert_eq!(Purification::TopK(2).apply([0.6, 0.4]), [0.6, 0.4]);
+        assert_eq!(Purification::Threshold(0.7).apply([0.6, 0.4]), [0.6, 0.4]);
+    }
+
+    #[test]
+    fn purifies_three_actions() {
+        let strategy = [0.0005, 0.4995, 0.5];
+
+        assert_eq!(Purification::None.apply(strategy), strategy);
+        assert_eq!(
+            Purification::default().apply(strategy),
+            [0.0, 0.4995 / 0.9995, 0.5 / 0.9995]
+        );
+        assert_eq!(Purification::Argmax.apply(strategy), [0.0, 0.0, 1.0]);
+        assert_eq!(Purification::Argmax.apply([0.5, 0.5, 0.0]), [1.0, 0.0, 0.0]);
+        assert_eq!(
+            Purification::TopK(2).apply([0.5, 0.25, 0.25]),
+            [2.0 / 3.0, 1.0 / 3.0, 0.0]
+        );
+        assert_eq!(
+            Purification::Threshold(0.9).apply([0.5, 0.5, 0.0]),
+            [0.5, 0.5, 0.0]
+        );
+    }
+}
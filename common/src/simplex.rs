@@ -0,0 +1,273 @@
+// Dense two-phase simplex method for small linear programs
+//
+// The programs are written as max c·z subject to rows a·z <= b or a·z = b with
+// z >= 0 and b >= 0, which is all the sequence-form programs need
+
+const EPSILON: f64 = 1e-9;
+
+// Consecutive pivots that leave the objective unchanged before switching from the
+// largest reduced cost to Bland's rule, which cannot cycle but crawls through the
+// degenerate vertices of the sequence-form programs
+const MAX_DEGENERATE_PIVOTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    LessEqual,
+    Equal,
+}
+
+#[derive(Debug, Clone)]
+pub struct LinearProgram {
+    pub objective: Vec<f64>,
+    pub constraints: Vec<(Vec<f64>, Relation, f64)>,
+}
+
+impl LinearProgram {
+    pub fn new(objective: Vec<f64>) -> LinearProgram {
+        LinearProgram {
+            objective,
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn add_constraint(&mut self, coefficients: Vec<f64>, relation: Relation, rhs: f64) {
+        assert_eq!(coefficients.len(), self.objective.len());
+        assert!(rhs >= 0.0, "the right-hand sides must not be negative");
+        self.constraints.push((coefficients, relation, rhs));
+    }
+
+    // The optimal value and the variables reaching it
+    pub fn maximize(&self) -> Result<(f64, Vec<f64>), String> {
+        let mut tableau = Tableau::new(self);
+
+        // Phase 1 drives the artificial variables of the equalities to zero
+        let costs: Vec<f64> = (0..tableau.n_columns)
+            .map(|j| {
+                if j >= tableau.first_artificial {
+                    -1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        tableau.optimize(&costs, tableau.n_columns)?;
+
+        if tableau.value(&costs) < -1e-7 {
+            return Err("the linear program is infeasible".to_string());
+        }
+        tableau.remove_artificials();
+
+        // Phase 2 optimizes the objective without letting the artificials back in
+        let mut costs = vec![0.0; tableau.n_columns];
+        costs[..self.objective.len()].copy_from_slice(&self.objective);
+        tableau.optimize(&costs, tableau.first_artificial)?;
+
+        let mut solution = vec![0.0; self.objective.len()];
+        for (row, &column) in tableau.basis.iter().enumerate() {
+            if column < solution.len() {
+                solution[column] = tableau.rhs(row);
+            }
+        }
+
+        Ok((tableau.value(&costs), solution))
+    }
+}
+
+// Rows of the constraints with one slack column per inequality and one artificial
+// column per equality, the right-hand side is the last column
+struct Tableau {
+    cells: Vec<f64>,
+    n_rows: usize,
+    n_columns: usize,
+    first_artificial: usize,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn new(program: &LinearProgram) -> Tableau {
+        let n_variables = program.objective.len();
+        let n_slacks = program
+            .constraints
+            .iter()
+            .filter(|(_, relation, _)| *relation == Relation::LessEqual)
+            .count();
+        let n_rows = program.constraints.len();
+        let n_columns = n_variables + n_rows;
+        let first_artificial = n_variables + n_slacks;
+
+        let mut tableau = Tableau {
+            cells: vec![0.0; n_rows * (n_columns + 1)],
+            n_rows,
+            n_columns,
+            first_artificial,
+            basis: Vec::with_capacity(n_rows),
+        };
+
+        let (mut slack, mut artificial) = (n_variables, first_artificial);
+        for (row, (coefficients, relation, rhs)) in program.constraints.iter().enumerate() {
+            let cells = tableau.row_mut(row);
+            cells[..n_variables].copy_from_slice(coefficients);
+            cells[n_columns] = *rhs;
+
+            let column = match relation {
+                Relation::LessEqual => &mut slack,
+                Relation::Equal => &mut artificial,
+            };
+            cells[*column] = 1.0;
+            tableau.basis.push(*column);
+            *column += 1;
+        }
+
+        tableau
+    }
+
+    fn row(&self, row: usize) -> &[f64] {
+        &self.cells[row * (self.n_columns + 1)..(row + 1) * (self.n_columns + 1)]
+    }
+
+    fn row_mut(&mut self, row: usize) -> &mut [f64] {
+        &mut self.cells[row * (self.n_columns + 1)..(row + 1) * (self.n_columns + 1)]
+    }
+
+    fn rhs(&self, row: usize) -> f64 {
+        self.row(row)[self.n_columns]
+    }
+
+    fn value(&self, costs: &[f64]) -> f64 {
+        (0..self.n_rows)
+            .map(|row| costs[self.basis[row]] * self.rhs(row))
+            .sum()
+    }
+
+    // Reduced cost of every column, the objective improves along the positive ones
+    fn reduced_costs(&self, costs: &[f64]) -> Vec<f64> {
+        let mut reduced = costs.to_vec();
+        for row in 0..self.n_rows {
+            let cost = costs[self.basis[row]];
+            if cost != 0.0 {
+                reduced
+                    .iter_mut()
+                    .zip(self.row(row).iter())
+                    .for_each(|(a, &b)| *a -= cost * b);
+            }
+        }
+        reduced
+    }
+
+    // Pivot until no column below `n_entering` improves the objective
+    fn optimize(&mut self, costs: &[f64], n_entering: usize) -> Result<(), String> {
+        let mut reduced = self.reduced_costs(costs);
+        let mut n_degenerate = 0;
+
+        loop {
+            let candidates = (0..n_entering).filter(|&j| reduced[j] > EPSILON);
+            let entering = if n_degenerate < MAX_DEGENERATE_PIVOTS {
+                candidates.max_by(|&a, &b| reduced[a].total_cmp(&reduced[b]))
+            } else {
+                candidates.min()
+            };
+            let Some(entering) = entering else {
+                return Ok(());
+            };
+
+            // Ratio test, ties go to the lowest basic column as in Bland's rule
+            let mut leaving: Option<(usize, f64)> = None;
+            for row in 0..self.n_rows {
+                let cell = self.row(row)[entering];
+                if cell > EPSILON {
+                    let ratio = self.rhs(row) / cell;
+                    let is_better = match leaving {
+                        None => true,
+                        Some((best, best_ratio)) => {
+                            ratio < best_ratio - EPSILON
+                                || (ratio < best_ratio + EPSILON
+                                    && self.basis[row] < self.basis[best])
+                        }
+                    };
+                    if is_better {
+                        leaving = Some((row, ratio));
+                    }
+                }
+            }
+            let Some((leaving, ratio)) = leaving else {
+                return Err("the linear program is unbounded".to_string());
+            };
+
+            n_degenerate = if ratio < EPSILON { n_degenerate + 1 } else { 0 };
+
+            self.pivot(leaving, entering);
+            let factor = reduced[entering];
+            reduced
+                .iter_mut()
+                .zip(self.row(leaving).iter())
+                .for_each(|(a, &b)| *a -= factor * b);
+        }
+    }
+
+    fn pivot(&mut self, pivot_row: usize, column: usize) {
+        let width = self.n_columns + 1;
+        let pivot = self.row(pivot_row)[column];
+        self.row_mut(pivot_row).iter_mut().for_each(|x| *x /= pivot);
+
+        let (before, rest) = self.cells.split_at_mut(pivot_row * width);
+        let (pivot_cells, after) = rest.split_at_mut(width);
+
+        for cells in before.chunks_mut(width).chain(after.chunks_mut(width)) {
+            let factor = cells[column];
+            if factor != 0.0 {
+                cells
+                    .iter_mut()
+                    .zip(pivot_cells.iter())
+                    .for_each(|(a, &b)| *a -= factor * b);
+            }
+        }
+
+        self.basis[pivot_row] = column;
+    }
+
+    // Swap the artificials still basic at zero for real columns, a row without any
+    // real column left is redundant and keeps its artificial at zero
+    fn remove_artificials(&mut self) {
+        for row in 0..self.n_rows {
+            if self.basis[row] < self.first_artificial {
+                continue;
+            }
+
+            let column = (0..self.first_artificial).find(|&j| self.row(row)[j].abs() > EPSILON);
+            if let Some(column) = column {
+                self.pivot(row, column);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_a_small_program() {
+        // max 3x + 2y, x + y <= 4, x + 3y <= 6, x - y = 1
+        let mut program = LinearProgram::new(vec![3.0, 2.0]);
+        program.add_constraint(vec![1.0, 1.0], Relation::LessEqual, 4.0);
+        program.add_constraint(vec![1.0, 3.0], Relation::LessEqual, 6.0);
+        program.add_constraint(vec![1.0, -1.0], Relation::Equal, 1.0);
+
+        let (value, solution) = program.maximize().unwrap();
+        assert!((value - 9.25).abs() < 1e-9);
+        assert!((solution[0] - 2.25).abs() < 1e-9);
+        assert!((solution[1] - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reports_infeasible_and_unbounded_programs() {
+        let mut infeasible = LinearProgram::new(vec![1.0]);
+        infeasible.add_constraint(vec![1.0], Relation::LessEqual, 1.0);
+        infeasible.add_constraint(vec![1.0], Relation::Equal, 2.0);
+        assert!(infeasible.maximize().is_err());
+
+        let mut unbounded = LinearProgram::new(vec![1.0, 0.0]);
+        unbounded.add_constraint(vec![-1.0, 1.0], Relation::LessEqual, 1.0);
+        assert!(unbounded.maximize().is_err());
+    }
+}
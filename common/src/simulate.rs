@@ -0,0 +1,182 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::agent::InfoSets;
+
+// A game tree seen from the simulations, `N` is the number of actions
+pub trait Game<const N: usize>: InfoSets {
+    fn root(&self) -> usize;
+    fn n_players(&self) -> usize;
+    // The player who acts at the information set `info_set`
+    fn player(&self, info_set: usize) -> usize;
+    fn node(&self, node: usize) -> Move<'_, N>;
+    // What `player` wins at the terminal node `node`
+    fn winnings(&self, node: usize, player: usize) -> f64;
+}
+
+// What happens at a node of the tree
+pub enum Move<'a, const N: usize> {
+    Terminal,
+    // Random moves with their probabilities and children
+    Chance(&'a [(f64, usize)]),
+    // `None` for the actions that are not legal
+    Decision {
+        info_set: usize,
+        children: [Option<usize>; N],
+    },
+}
+
+// Play one hand with everybody following `profile` and return the terminal node, the
+// deal and every action are drawn from `rng` so that a seeded generator replays the
+// same hands
+pub fn play_hand<T: Game<N>, R: Rng, const N: usize>(
+    tree: &T,
+    profile: &[[f64; N]],
+    rng: &mut R,
+) -> usize {
+    let mut node = tree.root();
+
+    loop {
+        node = match tree.node(node) {
+            Move::Terminal => return node,
+            Move::Chance(outcomes) => {
+                let weights: Vec<f64> = outcomes.iter().map(|&(pr, _)| pr).collect();
+                outcomes[sample(&weights, rng)].1
+            }
+            Move::Decision { info_set, children } => children[sample(&profile[info_set], rng)]
+                .expect("illegal actions have no probability"),
+        };
+    }
+}
+
+// Mean winnings of every player over `n_hands` hands
+pub fn simulate<T: Game<N>, R: Rng, const N: usize>(
+    tree: &T,
+    profile: &[[f64; N]],
+    n_hands: usize,
+    rng: &mut R,
+) -> Vec<f64> {
+    let mut total = vec![0.0; tree.n_players()];
+
+    for _ in 0..n_hands {
+        let node = play_hand(tree, profile, rng);
+        for (player, x) in total.iter_mut().enumerate() {
+            *x += tree.winnings(node, player);
+        }
+    }
+
+    total.into_iter().map(|x| x / n_hands as f64).collect()
+}
+
+// Mean of independent samples and its standard error
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    pub std_error: f64,
+}
+
+impl Estimate {
+    pub fn new(samples: &[f64]) -> Estimate {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0);
+
+        Estimate {
+            mean,
+            std_error: (variance / n).sqrt(),
+        }
+    }
+
+    // 95% confidence interval of the mean from the normal approximation
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        (
+            self.mean - 1.96 * self.std_error,
+            self.mean + 1.96 * self.std_error,
+        )
+    }
+}
+
+// The profile where `a` plays `seat` and `b` plays every other seat
+pub fn seat_profile<T: Game<N>, const N: usize>(
+    tree: &T,
+    a: &[[f64; N]],
+    b: &[[f64; N]],
+    seat: usize,
+) -> Vec<[f64; N]> {
+    (0..tree.n_info_sets())
+        .map(|i| if tree.player(i) == seat { a[i] } else { b[i] })
+        .collect()
+}
+
+// Play `n_deals` deals between `a` and `b` with duplicate dealing: every deal is played
+// once with `a` in each seat and `b` in the others, so that the luck of the cards
+// cancels out. Returns what `a` wins per hand on each deal
+pub fn duplicate_match<T: Game<N>, R: Rng, const N: usize>(
+    tree: &T,
+    a: &[[f64; N]],
+    b: &[[f64; N]],
+    n_deals: usize,
+    rng: &mut R,
+) -> Vec<f64> {
+    let profiles: Vec<_> = (0..tree.n_players())
+        .map(|seat| seat_profile(tree, a, b, seat))
+        .collect();
+
+    (0..n_deals)
+        .map(|_| {
+            let deal_seed = rng.gen::<u64>();
+            let total: f64 = profiles
+                .iter()
+                .enumerate()
+                .map(|(seat, profile)| {
+                    let mut deal = StdRng::seed_from_u64(deal_seed);
+                    let node = play_deal(tree, profile, &mut deal, rng);
+                    tree.winnings(node, seat)
+                })
+                .sum();
+
+            total / tree.n_players() as f64
+        })
+        .collect()
+}
+
+// Like `play_hand` with the cards drawn from `deal` and the actions from `rng`, so the
+// same cards can be dealt again
+fn play_deal<T: Game<N>, D: Rng, R: Rng, const N: usize>(
+    tree: &T,
+    profile: &[[f64; N]],
+    deal: &mut D,
+    rng: &mut R,
+) -> usize {
+    let mut node = tree.root();
+
+    loop {
+        node = match tree.node(node) {
+            Move::Terminal => return node,
+            Move::Chance(outcomes) => {
+                let weights: Vec<f64> = outcomes.iter().map(|&(pr, _)| pr).collect();
+                outcomes[sample(&weights, deal)].1
+            }
+            Move::Decision { info_set, children } => children[sample(&profile[info_set], rng)]
+                .expect("illegal actions have no probability"),
+        };
+    }
+}
+
+// Draw an index with probabilities proportional to `weights`
+pub fn sample<R: Rng>(weights: &[f64], rng: &mut R) -> usize {
+    let mut x = rng.gen::<f64>() * weights.iter().sum::<f64>();
+
+    for (i, &weight) in weights.iter().enumerate() {
+        if x < weight {
+            return i;
+        }
+        x -= weight;
+    }
+
+    // Rounding can leave a sliver past the last weight
+    weights
+        .iter()
+        .rposition(|&weight| weight > 0.0)
+        .unwrap_or(0)
+}
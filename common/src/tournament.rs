@@ -0,0 +1,284 @@
+use std::cmp::Ordering;
+
+// Round robin between strategy files, e.g. `--tournament results --strategy a.txt
+// --strategy b.txt --strategy c.txt`
+//
+// Every pair plays a duplicate match, the game decides how the deals are played.
+// A match scores 1 for the strategy with positive mean winnings, 0.5 for a draw
+// and 0 for a loss, so the ratings rank the strategies the way the winnings
+// matrix does. Elo ratings are fitted to the scores of all the matches at once so
+// that the order the matches are played in does not matter.
+
+pub const MEAN_RATING: f64 = 1500.0;
+
+#[derive(Debug, Clone)]
+pub struct Tournament {
+    pub names: Vec<String>,
+    // What the row strategy wins per hand against the column strategy
+    pub winnings: Vec<Vec<f64>>,
+    // Share of the deals the row strategy wins money on against the column strategy,
+    // a tied deal counts as half a win
+    pub win_rates: Vec<Vec<f64>>,
+    // Result of the match of the row strategy against the column strategy
+    pub scores: Vec<Vec<f64>>,
+    pub ratings: Vec<f64>,
+}
+
+impl Tournament {
+    // `play_match(i, j)` gives what the strategy `i` wins on every deal of its match
+    // against the strategy `j`
+    pub fn new(
+        names: Vec<String>,
+        mut play_match: impl FnMut(usize, usize) -> Vec<f64>,
+    ) -> Tournament {
+        let n = names.len();
+        let mut winnings = vec![vec![0.0; n]; n];
+        let mut win_rates = vec![vec![0.5; n]; n];
+        let mut scores = vec![vec![0.5; n]; n];
+
+        for i in 0..n {
+            for j in i + 1..n {
+                let samples = play_match(i, j);
+                let win_rate = samples
+                    .iter()
+                    .map(|&x| match x.total_cmp(&0.0) {
+                        Ordering::Greater => 1.0,
+                        Ordering::Equal => 0.5,
+                        Ordering::Less => 0.0,
+                    })
+                    .sum::<f64>()
+                    / samples.len() as f64;
+
+                // Zero-sum, one match gives both cells
+                let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+                let score = if mean > 0.0 {
+                    1.0
+                } else if mean < 0.0 {
+                    0.0
+                } else {
+                    0.5
+                };
+                winnings[i][j] = mean;
+                winnings[j][i] = -mean;
+                win_rates[i][j] = win_rate;
+                win_rates[j][i] = 1.0 - win_rate;
+                scores[i][j] = score;
+                scores[j][i] = 1.0 - score;
+            }
+        }
+
+        Tournament {
+            names,
+            ratings: elo_ratings(&scores),
+            winnings,
+            win_rates,
+            scores,
+        }
+    }
+
+    // Indices of the strategies from the highest rating to the lowest
+    pub fn standings(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| self.ratings[b].total_cmp(&self.ratings[a]));
+        order
+    }
+
+    // Mean over the opponents of a row of `winnings`, `win_rates` or `scores`
+    fn mean_against_field(&self, values: &[Vec<f64>], i: usize) -> f64 {
+        let total: f64 = (0..self.names.len())
+            .filter(|&j| j != i)
+            .map(|j| values[i][j])
+            .sum();
+        total / (self.names.len() - 1).max(1) as f64
+    }
+
+    // One row per strategy, the best first
+    fn rating_rows(&self) -> Vec<[String; 6]> {
+        self.standings()
+            .into_iter()
+            .enumerate()
+            .map(|(rank, i)| {
+                [
+                    (rank + 1).to_string(),
+                    self.names[i].clone(),
+                    format!("{:.0}", self.ratings[i]),
+                    format!("{:.4}", self.mean_against_field(&self.scores, i)),
+                    format!("{:.4}", self.mean_against_field(&self.winnings, i)),
+                    format!("{:.4}", self.mean_against_field(&self.win_rates, i)),
+                ]
+            })
+            .collect()
+    }
+
+    // `values` of each row strategy against each column, e.g. the winnings per hand,
+    // in the order of the standings and empty on the diagonal
+    fn matrix_rows(&self, values: &[Vec<f64>]) -> Vec<Vec<String>> {
+        let order = self.standings();
+
+        order
+            .iter()
+            .map(|&i| {
+                let mut row = vec![self.names[i].clone()];
+                row.extend(order.iter().map(|&j| {
+                    if i == j {
+                        String::new()
+                    } else {
+                        format!("{:.4}", values[i][j])
+                    }
+                }));
+                row
+            })
+            .collect()
+    }
+
+    fn matrix_header(&self) -> Vec<String> {
+        let mut header = vec!["strategy".to_string()];
+        header.extend(self.standings().into_iter().map(|i| self.names[i].clone()));
+        header
+    }
+
+    pub fn ratings_csv(&self) -> String {
+        csv(&RATING_HEADER.map(String::from), &self.rating_rows())
+    }
+
+    pub fn ratings_markdown(&self) -> String {
+        markdown(&RATING_HEADER.map(String::from), &self.rating_rows())
+    }
+
+    pub fn winnings_csv(&self) -> String {
+        csv(&self.matrix_header(), &self.matrix_rows(&self.winnings))
+    }
+
+    pub fn winnings_markdown(&self) -> String {
+        markdown(&self.matrix_header(), &self.matrix_rows(&self.winnings))
+    }
+
+    pub fn win_rates_csv(&self) -> String {
+        csv(&self.matrix_header(), &self.matrix_rows(&self.win_rates))
+    }
+
+    pub fn win_rates_markdown(&self) -> String {
+        markdown(&self.matrix_header(), &self.matrix_rows(&self.win_rates))
+    }
+}
+
+const RATING_HEADER: [&str; 6] = [
+    "rank", "strategy", "rating", "score", "winnings", "win rate",
+];
+
+// Elo ratings whose expected scores match the observed ones, the maximum likelihood
+// of the Bradley-Terry model found by gradient steps and centred on `MEAN_RATING`.
+// Scores are kept within [0.01, 0.99] so that a strategy that wins every match still
+// gets a finite rating
+pub fn elo_ratings(scores: &[Vec<f64>]) -> Vec<f64> {
+    let n = scores.len();
+    let mut ratings = vec![MEAN_RATING; n];
+    if n < 2 {
+        return ratings;
+    }
+
+    let step = 200.0 / (n - 1) as f64;
+    for _ in 0..10000 {
+        let gradient: Vec<f64> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .filter(|&j| j != i)
+                    .map(|j| {
+                        scores[i][j].clamp(0.01, 0.99) - expected_score(ratings[i], ratings[j])
+                    })
+                    .sum()
+            })
+            .collect();
+
+        for (rating, slope) in ratings.iter_mut().zip(gradient) {
+            *rating += step * slope;
+        }
+    }
+
+    let mean = ratings.iter().sum::<f64>() / n as f64;
+    ratings.iter().map(|x| x - mean + MEAN_RATING).collect()
+}
+
+// Expected score of a player rated `a` against one rated `b`
+pub fn expected_score(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+// Fields with a comma or a quote are quoted
+fn csv(header: &[String], rows: &[impl AsRef<[String]>]) -> String {
+    let line = |fields: &[String]| {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| {
+                if field.contains([',', '"', '\n']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.clone()
+                }
+            })
+            .collect();
+        fields.join(",") + "\n"
+    };
+
+    line(header)
+        + &rows
+            .iter()
+            .map(|row| line(row.as_ref()))
+            .collect::<String>()
+}
+
+fn markdown(header: &[String], rows: &[impl AsRef<[String]>]) -> String {
+    let line = |fields: &[String]| {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| field.replace('|', "\\|"))
+            .collect();
+        format!("| {} |\n", fields.join(" | "))
+    };
+    let separator = format!("|{}\n", "---|".repeat(header.len()));
+
+    line(header)
+        + &separator
+        + &rows
+            .iter()
+            .map(|row| line(row.as_ref()))
+            .collect::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elo_ratings_match_the_expected_scores() {
+        let truth = [1300.0, 1500.0, 1550.0, 1650.0];
+        let scores: Vec<Vec<f64>> = truth
+            .iter()
+            .map(|&a| truth.iter().map(|&b| expected_score(a, b)).collect())
+            .collect();
+
+        for (rating, expected) in elo_ratings(&scores).iter().zip(truth) {
+            assert!(
+                (rating - expected).abs() < 1.0,
+                "{} != {}",
+                rating,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn matches_are_scored_on_the_mean_winnings() {
+        // The first strategy wins most deals but loses money on the match
+        let samples = vec![1.0, 1.0, -5.0];
+        let tournament = Tournament::new(vec!["a".to_string(), "b".to_string()], |i, j| {
+            assert_eq!((i, j), (0, 1));
+            samples.clone()
+        });
+
+        assert_eq!(tournament.scores, [[0.5, 0.0], [1.0, 0.5]]);
+        assert_eq!(tournament.win_rates[0][1], 2.0 / 3.0);
+        assert_eq!(tournament.winnings[1][0], 1.0);
+        assert_eq!(tournament.standings(), [1, 0]);
+    }
+}
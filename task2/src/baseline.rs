@@ -0,0 +1,104 @@
+use std::fmt;
+use std::str::FromStr;
+
+use common::agent::Agent;
+
+use crate::tree::GameTree;
+use crate::{is_facing_bet, N_ACTIONS};
+
+// Rule-based bots to benchmark the trained strategies against, e.g. `--baselines`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Baseline {
+    // Checks and calls every bet
+    AlwaysCall,
+    // Bets and calls every bet
+    AlwaysRaise,
+    // Picks either action with the same probability
+    Random,
+    // Bets and calls with the highest rank, otherwise plays like `AlwaysCall`
+    RaiseWithKing,
+    // Bets and calls when the rank of its card, scaled to [0, 1], reaches the
+    // threshold, otherwise checks and folds
+    HandStrength(f64),
+}
+
+pub const BASELINES: [Baseline; 5] = [
+    Baseline::AlwaysCall,
+    Baseline::AlwaysRaise,
+    Baseline::Random,
+    Baseline::RaiseWithKing,
+    Baseline::HandStrength(0.5),
+];
+
+const CHECK: [f64; N_ACTIONS] = [1.0, 0.0];
+const BET: [f64; N_ACTIONS] = [0.0, 1.0];
+
+impl Agent<GameTree, N_ACTIONS> for Baseline {
+    fn strategy(&self, tree: &GameTree, info_set: usize) -> [f64; N_ACTIONS] {
+        let info_set = &tree.info_sets[info_set];
+        let facing_bet = is_facing_bet(&info_set.history);
+        let call = if facing_bet { BET } else { CHECK };
+        let strength = info_set.card as f64 / (tree.n_ranks - 1) as f64;
+
+        match *self {
+            Baseline::AlwaysCall => call,
+            Baseline::AlwaysRaise => BET,
+            Baseline::Random => [1.0 / N_ACTIONS as f64; N_ACTIONS],
+            Baseline::RaiseWithKing => {
+                if info_set.card as usize == tree.n_ranks - 1 {
+                    BET
+                } else {
+                    call
+                }
+            }
+            Baseline::HandStrength(threshold) => {
+                if strength >= threshold {
+                    BET
+                } else {
+                    CHECK
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Baseline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Baseline, String> {
+        let threshold = |threshold: &str| {
+            threshold
+                .parse::<f64>()
+                .ok()
+                .filter(|threshold| (0.0..=1.0).contains(threshold))
+                .ok_or_else(|| format!("expected a threshold between 0 and 1 in baseline {}", s))
+        };
+
+        match s {
+            "call" => Ok(Baseline::AlwaysCall),
+            "raise" => Ok(Baseline::AlwaysRaise),
+            "random" => Ok(Baseline::Random),
+            "king" => Ok(Baseline::RaiseWithKing),
+            "strength" => Ok(Baseline::HandStrength(0.5)),
+            _ => match s.split_once(':') {
+                Some(("strength", value)) => Ok(Baseline::HandStrength(threshold(value)?)),
+                _ => Err(format!(
+                    "unknown baseline {}, expected call, raise, random, king or strength:THRESHOLD",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Baseline::AlwaysCall => write!(f, "call"),
+            Baseline::AlwaysRaise => write!(f, "raise"),
+            Baseline::Random => write!(f, "random"),
+            Baseline::RaiseWithKing => write!(f, "king"),
+            Baseline::HandStrength(threshold) => write!(f, "strength:{}", threshold),
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use crate::tree::{GameTree, Node};
+use crate::{add_scaled, Utility, MAX_PLAYERS, N_ACTIONS};
+
+// Utility of every player when everybody follows `profile`, where `profile[i]` is
+// the strategy played at information set `i`
+pub fn expected_utility(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> Utility {
+    node_utility(tree, profile, tree.root)
+}
+
+fn node_utility(tree: &GameTree, profile: &[[f64; N_ACTIONS]], node: usize) -> Utility {
+    let mut util = [0.0; MAX_PLAYERS];
+
+    match &tree.nodes[node] {
+        Node::Terminal { utility } => return *utility,
+        Node::Chance { outcomes } => {
+            for &(pr, child) in outcomes {
+                add_scaled(&mut util, &node_utility(tree, profile, child), pr);
+            }
+        }
+        Node::Decision {
+            info_set, children, ..
+        } => {
+            for (&child, &pr) in children.iter().zip(profile[*info_set].iter()) {
+                add_scaled(&mut util, &node_utility(tree, profile, child), pr);
+            }
+        }
+    }
+
+    util
+}
+
+// Probability of reaching each information set when everybody follows `profile`
+pub fn reach_probabilities(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> Vec<f64> {
+    let mut reach = vec![0.0; tree.info_sets.len()];
+    collect_reach(tree, profile, tree.root, 1.0, &mut reach);
+    reach
+}
+
+fn collect_reach(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    node: usize,
+    pr: f64,
+    reach: &mut [f64],
+) {
+    match &tree.nodes[node] {
+        Node::Terminal { .. } => {}
+        Node::Chance { outcomes } => {
+            for &(child_pr, child) in outcomes {
+                collect_reach(tree, profile, child, pr * child_pr, reach);
+            }
+        }
+        Node::Decision {
+            info_set, children, ..
+        } => {
+            reach[*info_set] += pr;
+
+            for (&child, &action_pr) in children.iter().zip(profile[*info_set].iter()) {
+                collect_reach(tree, profile, child, pr * action_pr, reach);
+            }
+        }
+    }
+}
+
+// A pure strategy of one player that maximises their utility while the others
+// keep following a profile
+#[derive(Debug, Clone)]
+pub struct BestResponse {
+    pub value: f64,
+    // The action picked at every information set of the player, `None` for the others
+    pub actions: Vec<Option<usize>>,
+}
+
+pub fn best_response(tree: &GameTree, profile: &[[f64; N_ACTIONS]], player: usize) -> BestResponse {
+    let mut solver = BestResponseSolver::new(tree, profile, player);
+    let value = solver.value(tree.root);
+
+    let actions = tree
+        .info_sets
+        .iter()
+        .enumerate()
+        .map(|(i, info_set)| (info_set.player == player).then(|| solver.action(i)))
+        .collect();
+
+    BestResponse { value, actions }
+}
+
+// How much a player gains on average by switching to a best response, zero at an
+// equilibrium
+pub fn exploitability(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> f64 {
+    let utility = expected_utility(tree, profile);
+
+    let gain: f64 = (0..tree.n_players)
+        .map(|player| best_response(tree, profile, player).value - utility[player])
+        .sum();
+
+    gain / tree.n_players as f64
+}
+
+// The best action of an information set depends on the values below every node in
+// it, so both are computed lazily and cached
+struct BestResponseSolver<'a> {
+    tree: &'a GameTree,
+    profile: &'a [[f64; N_ACTIONS]],
+    player: usize,
+    // Probability of chance and the other players reaching each node
+    reach_pr: Vec<f64>,
+    // Nodes of each information set of `player`
+    members: Vec<Vec<usize>>,
+    values: Vec<Option<f64>>,
+    actions: Vec<Option<usize>>,
+}
+
+impl<'a> BestResponseSolver<'a> {
+    fn new(
+        tree: &'a GameTree,
+        profile: &'a [[f64; N_ACTIONS]],
+        player: usize,
+    ) -> BestResponseSolver<'a> {
+        let mut solver = BestResponseSolver {
+            tree,
+            profile,
+            player,
+            reach_pr: vec![0.0; tree.nodes.len()],
+            members: vec![Vec::new(); tree.info_sets.len()],
+            values: vec![None; tree.nodes.len()],
+            actions: vec![None; tree.info_sets.len()],
+        };
+
+        solver.collect(tree.root, 1.0);
+        solver
+    }
+
+    fn collect(&mut self, node: usize, pr: f64) {
+        self.reach_pr[node] = pr;
+
+        match &self.tree.nodes[node] {
+            Node::Terminal { .. } => {}
+            Node::Chance { outcomes } => {
+                for &(child_pr, child) in outcomes {
+                    self.collect(child, pr * child_pr);
+                }
+            }
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                if *player == self.player {
+                    self.members[*info_set].push(node);
+                }
+
+                for (i, &child) in children.iter().enumerate() {
+                    let child_pr = if *player == self.player {
+                        1.0
+                    } else {
+                        self.profile[*info_set][i]
+                    };
+                    self.collect(child, pr * child_pr);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, node: usize) -> f64 {
+        if let Some(value) = self.values[node] {
+            return value;
+        }
+
+        let value = match &self.tree.nodes[node] {
+            Node::Terminal { utility } => utility[self.player],
+            Node::Chance { outcomes } => outcomes
+                .iter()
+                .map(|&(pr, child)| pr * self.value(child))
+                .sum(),
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                if *player == self.player {
+                    let action = self.action(*info_set);
+                    self.value(children[action])
+                } else {
+                    children
+                        .iter()
+                        .zip(self.profile[*info_set].iter())
+                        .map(|(&child, &pr)| pr * self.value(child))
+                        .sum()
+                }
+            }
+        };
+
+        self.values[node] = Some(value);
+        value
+    }
+
+    // The action with the highest value summed over the nodes of the information
+    // set, weighted by how likely the others make each node, ties go to the first one
+    fn action(&mut self, info_set: usize) -> usize {
+        if let Some(action) = self.actions[info_set] {
+            return action;
+        }
+
+        let mut action_values = [0.0; N_ACTIONS];
+        for node in self.members[info_set].clone() {
+            let Node::Decision { children, .. } = &self.tree.nodes[node] else {
+                unreachable!("information sets only hold decision nodes");
+            };
+
+            for (i, &child) in children.iter().enumerate() {
+                action_values[i] += self.reach_pr[node] * self.value(child);
+            }
+        }
+
+        let mut action = 0;
+        for (i, &value) in action_values.iter().enumerate() {
+            if value > action_values[action] {
+                action = i;
+            }
+        }
+
+        self.actions[info_set] = Some(action);
+        action
+    }
+}
@@ -0,0 +1,93 @@
+use crate::MAX_PLAYERS;
+
+// Ranks from the lowest to the highest, a game with N ranks uses the top N of them
+const RANK_NAMES: [&str; 12] = ["2", "3", "4", "5", "6", "7", "8", "9", "T", "J", "Q", "K"];
+
+// Parameters of generalised Kuhn poker, the deck holds one card of each rank
+#[derive(Debug, Clone, PartialEq)]
+pub struct KuhnConfig {
+    pub n_players: usize,
+    pub n_ranks: usize,
+    pub ante: f64,
+    pub bet: f64,
+}
+
+impl Default for KuhnConfig {
+    fn default() -> KuhnConfig {
+        KuhnConfig {
+            n_players: 2,
+            n_ranks: 3,
+            ante: 1.0,
+            bet: 1.0,
+        }
+    }
+}
+
+impl KuhnConfig {
+    pub fn new(
+        n_players: usize,
+        n_ranks: usize,
+        ante: f64,
+        bet: f64,
+    ) -> Result<KuhnConfig, String> {
+        if !(2..=MAX_PLAYERS).contains(&n_players) {
+            return Err(format!(
+                "the number of players must be between 2 and {}, got {}",
+                MAX_PLAYERS, n_players
+            ));
+        }
+
+        if !(n_players..=RANK_NAMES.len()).contains(&n_ranks) {
+            return Err(format!(
+                "the number of ranks must be between {} and {}, got {}",
+                n_players,
+                RANK_NAMES.len(),
+                n_ranks
+            ));
+        }
+
+        if ante <= 0.0 || bet <= 0.0 {
+            return Err(format!(
+                "the ante and the bet must be positive, got ante {} and bet {}",
+                ante, bet
+            ));
+        }
+
+        Ok(KuhnConfig {
+            n_players,
+            n_ranks,
+            ante,
+            bet,
+        })
+    }
+
+    // Every way to give a different card to each player, all of them equally likely
+    pub fn deals(&self) -> Vec<Vec<isize>> {
+        let mut deals = vec![Vec::new()];
+
+        for _ in 0..self.n_players {
+            let mut next = Vec::new();
+
+            for deal in &deals {
+                for card in 0..self.n_ranks as isize {
+                    if !deal.contains(&card) {
+                        next.push([deal.as_slice(), &[card]].concat());
+                    }
+                }
+            }
+
+            deals = next;
+        }
+
+        deals
+    }
+
+    pub fn card_str(&self, card: isize) -> &'static str {
+        card_name(self.n_ranks, card)
+    }
+}
+
+// Name of the card of rank `card` in a game with `n_ranks` ranks
+pub fn card_name(n_ranks: usize, card: isize) -> &'static str {
+    RANK_NAMES[RANK_NAMES.len() - n_ranks + card as usize]
+}
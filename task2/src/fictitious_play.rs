@@ -0,0 +1,72 @@
+use crate::best_response::best_response;
+use crate::info_set::InfoSetTable;
+use crate::tree::{GameTree, Node};
+use crate::{MAX_PLAYERS, N_ACTIONS};
+
+// One iteration of extensive-form fictitious play, the players best respond in turn
+// to the average profile and each best response is averaged into `table` before
+// the next player responds to it
+//
+// Each best response is weighted by how likely its player reaches the information
+// set, so the average strategy is the mix of all the best responses played so far.
+// The first response is to the uniform strategy of an empty table
+pub fn fictitious_play(tree: &GameTree, table: &mut InfoSetTable) {
+    for player in 0..tree.n_players {
+        let mut response = table.average_profile();
+        let actions = best_response(tree, &response, player).actions;
+
+        for (strategy, action) in response.iter_mut().zip(actions.iter()) {
+            if let Some(action) = *action {
+                *strategy = [0.0; N_ACTIONS];
+                strategy[action] = 1.0;
+            }
+        }
+
+        // Only the information sets of the player are updated, their reach does not
+        // depend on the other players
+        let reach = own_reach(tree, &response);
+        for (i, action) in actions.iter().enumerate() {
+            if action.is_some() {
+                table.add_average(i, &response[i], reach[i]);
+            }
+        }
+    }
+}
+
+// Probability of the player of each information set playing to it under `profile`
+fn own_reach(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> Vec<f64> {
+    let mut reach = vec![0.0; tree.info_sets.len()];
+    collect_own_reach(tree, profile, tree.root, [1.0; MAX_PLAYERS], &mut reach);
+    reach
+}
+
+fn collect_own_reach(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    node: usize,
+    pr: [f64; MAX_PLAYERS],
+    reach: &mut [f64],
+) {
+    match &tree.nodes[node] {
+        Node::Terminal { .. } => {}
+        Node::Chance { outcomes } => {
+            for &(_, child) in outcomes {
+                collect_own_reach(tree, profile, child, pr, reach);
+            }
+        }
+        Node::Decision {
+            player,
+            info_set,
+            children,
+        } => {
+            // Perfect recall, every node of the information set has the same value
+            reach[*info_set] = pr[*player];
+
+            for (&child, &action_pr) in children.iter().zip(profile[*info_set].iter()) {
+                let mut child_pr = pr;
+                child_pr[*player] *= action_pr;
+                collect_own_reach(tree, profile, child, child_pr, reach);
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::str::FromStr;
+
+use crate::tree::GameTree;
+use crate::N_ACTIONS;
+
+// A strategy forced on an information set, e.g. `--lock "J rrc=0,1"` always calls the
+// bet with the Jack
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lock {
+    pub key: String,
+    pub strategy: [f64; N_ACTIONS],
+}
+
+impl Lock {
+    // Index of the locked information set in `GameTree::info_sets`
+    pub fn info_set(&self, tree: &GameTree) -> Result<usize, String> {
+        tree.info_sets
+            .iter()
+            .position(|info_set| info_set.key == self.key)
+            .ok_or_else(|| format!("unknown information set '{}'", self.key))
+    }
+}
+
+impl FromStr for Lock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Lock, String> {
+        let (key, probabilities) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected KEY=P1,P2 in lock '{}'", s))?;
+
+        let probabilities = probabilities
+            .split(',')
+            .map(|x| x.trim().parse::<f64>().ok().filter(|&x| x >= 0.0))
+            .collect::<Option<Vec<f64>>>()
+            .filter(|probabilities| probabilities.len() == N_ACTIONS)
+            .ok_or_else(|| {
+                format!(
+                    "expected {} non-negative probabilities in lock '{}'",
+                    N_ACTIONS, s
+                )
+            })?;
+
+        let total = probabilities.iter().sum::<f64>();
+        if total <= 0.0 {
+            return Err(format!("the probabilities of lock '{}' sum to zero", s));
+        }
+
+        let mut strategy = [0.0; N_ACTIONS];
+        strategy
+            .iter_mut()
+            .zip(probabilities.iter())
+            .for_each(|(a, &b)| *a = b / total);
+
+        Ok(Lock {
+            key: key.trim().to_string(),
+            strategy,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_normalises_locks() {
+        let lock: Lock = "J rrc=1,3".parse().unwrap();
+
+        assert_eq!(lock.key, "J rrc");
+        assert_eq!(lock.strategy, [0.25, 0.75]);
+        assert!("J rrc".parse::<Lock>().is_err());
+        assert!("J rrc=1".parse::<Lock>().is_err());
+        assert!("J rrc=0,0".parse::<Lock>().is_err());
+        assert!("J rrc=-1,2".parse::<Lock>().is_err());
+    }
+}
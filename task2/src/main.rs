@@ -1,283 +1,1648 @@
-use std::collections::HashMap;
-use std::fmt;
+mod baseline;
+mod best_response;
+mod config;
+mod fictitious_play;
+mod info_set;
+mod locking;
+mod opponent;
+mod play;
+mod restricted;
+mod sequence_form;
+mod strategy_file;
+mod tree;
+mod workers;
 
-const N_ACTIONS: usize = 2;
-const N_CARDS: usize = 3;
+use std::fmt::Display;
+use std::io;
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
 
-const CHANCE_ACTIONS: [&str; N_ACTIONS] = ["c", "b"]; // There are three actions, 'c' => call, 'bet' => bet
+use common::agent::Agent;
+use common::minimizer::RegretMinimizer;
+use common::pruning::Pruning;
+use common::purification::Purification;
+use common::simulate::{duplicate_match, simulate, Estimate};
+use common::tournament::Tournament;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use baseline::{Baseline, BASELINES};
+use best_response::{expected_utility, exploitability, reach_probabilities};
+use config::KuhnConfig;
+use fictitious_play::fictitious_play;
+use info_set::{InfoSetTable, RegretBuffer};
+use locking::Lock;
+use opponent::{exploit, OpponentModel, OpponentSource};
+use play::play;
+use restricted::{evaluate, lock_model, restricted_tree};
+use tree::{GameTree, Node};
+use workers::with_workers;
+
+pub const N_ACTIONS: usize = 2;
+pub const MAX_PLAYERS: usize = 4;
+
+pub const CHANCE_ACTIONS: [&str; N_ACTIONS] = ["c", "b"]; // There are three actions, 'c' => call, 'bet' => bet
+
+// Utility of every player, the seats past `KuhnConfig::n_players` stay at zero
+pub type Utility = [f64; MAX_PLAYERS];
 
 fn main() {
-    let mut i_map: HashMap<String, InformationSet> = HashMap::new();
-    let n_iterations = 10000;
-    let mut expected_game_value = 0.0;
+    let options = Options::from_args();
 
-    for _ in 0..n_iterations {
-        expected_game_value += cfr(&mut i_map, "", -1, -1, 1.0, 1.0, 1.0);
+    if options.bench {
+        bench(&options);
+        return;
+    }
 
-        for v in i_map.values_mut() {
-            v.next_strategy();
-        }
+    let tree = GameTree::new(&options.config);
+
+    if let Some((a, b)) = &options.matchup {
+        match_session(&options, &tree, a, b);
+        return;
     }
 
-    expected_game_value /= n_iterations as f64;
-    display_results(expected_game_value, &i_map);
-}
+    if !options.baselines.is_empty() && !options.strategies.is_empty() {
+        let agents: Vec<_> = options
+            .strategies
+            .iter()
+            .map(|path| {
+                let profile = strategy_file::load(&tree, path).unwrap_or_else(|error| {
+                    eprintln!("{}", error);
+                    process::exit(2);
+                });
+                (path.as_str(), profile)
+            })
+            .collect();
+        baseline_report(&options, &tree, &agents);
+        return;
+    }
 
-// The CFR (Counter Factual Regret Minimization) algorithm
-pub fn cfr(
-    i_map: &mut HashMap<String, InformationSet>,
-    history: &str,
-    card_1: isize,
-    card_2: isize,
-    pr_1: f64,
-    pr_2: f64,
-    pr_c: f64,
-) -> f64 {
-    if is_chance_node(history) {
-        return chance_util(i_map);
+    if let Some(prefix) = &options.tournament {
+        tournament_session(&options, &tree, prefix);
+        return;
     }
 
-    if is_terminal(history) {
-        return terminal_util(history, card_1, card_2);
+    let mut table = InfoSetTable::new(tree.info_sets.len())
+        .with_minimizer(options.minimizer)
+        .with_pruning(options.pruning);
+
+    for lock in &options.locks {
+        let info_set = lock.info_set(&tree).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
+        table.lock(info_set, &lock.strategy);
     }
 
-    let n = history.len();
-    let is_player_1 = n % 2 == 0;
+    let opponent = options.opponent.as_ref().map(|source| {
+        let model = OpponentModel::load(&tree, source).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
+        (source, model)
+    });
 
-    let (key, mut info_set) =
-        get_info_set(i_map, if is_player_1 { card_1 } else { card_2 }, history);
+    if options.solver == Solver::SequenceForm {
+        let (_, profile) = sequence_form::solve(&tree).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
 
-    let strategy = &info_set.strategy;
+        for (i, strategy) in profile.iter().enumerate() {
+            table.add_average(i, strategy, 1.0);
+        }
 
-    info_set.reach_pr += if is_player_1 { pr_1 } else { pr_2 };
+        if options.play {
+            play_session(&options, &tree, &table);
+            return;
+        }
 
-    let mut action_utils = [0.0; N_ACTIONS];
+        save_strategies(&options, &tree, &table);
 
-    for (i, action) in CHANCE_ACTIONS.iter().enumerate() {
-        let next_history = format!("{}{}", history, action);
-        if is_player_1 {
-            action_utils[i] = -1.0
-                * cfr(
-                    i_map,
-                    &next_history,
-                    card_1,
-                    card_2,
-                    pr_1 * strategy[i],
-                    pr_2,
-                    pr_c,
-                );
-        } else {
-            action_utils[i] = -1.0
-                * cfr(
-                    i_map,
-                    &next_history,
-                    card_1,
-                    card_2,
-                    pr_1,
-                    pr_2 * strategy[i],
-                    pr_c,
+        let expected_game_value = expected_utility(&tree, &profile);
+        display_results(&options, expected_game_value, &tree, &table);
+
+        if let Some((source, model)) = &opponent {
+            opponent_report(&options, &tree, &table, source, model);
+            restricted_nash_report(&options, &tree, source, model);
+        }
+
+        if !options.baselines.is_empty() {
+            let profile = table.purified_profile(options.purification);
+            baseline_report(&options, &tree, &[("the trained strategy", profile)]);
+        }
+        return;
+    }
+
+    let n_iterations = options.n_iterations;
+    let mut expected_game_value = [0.0; MAX_PLAYERS];
+
+    with_workers(&tree, options.n_threads, |workers| {
+        for i in 0..n_iterations {
+            match options.solver {
+                Solver::Cfr => {
+                    let utility = workers.chance_util(&mut table);
+                    add_scaled(&mut expected_game_value, &utility, 1.0);
+
+                    table.next_strategy();
+                }
+                Solver::FictitiousPlay => fictitious_play(&tree, &mut table),
+                Solver::SequenceForm => unreachable!("the sequence form is solved at once"),
+            }
+
+            if options.report_every > 0 && (i + 1) % options.report_every == 0 {
+                println!(
+                    "iteration {}, exploitability: {}",
+                    i + 1,
+                    exploitability(&tree, &table.average_profile())
                 );
+            }
         }
+    });
+
+    if options.play {
+        play_session(&options, &tree, &table);
+        return;
     }
 
-    let util = action_utils
-        .iter()
-        .zip(strategy.iter())
-        .map(|(&x, &y)| x * y)
-        .sum();
-    let regrets: Vec<f64> = action_utils
-        .iter()
-        .zip(strategy.iter())
-        .map(|(&_x, &_y)| _x - util)
-        .collect();
+    // Fictitious play only keeps the average profile, its value is computed exactly
+    let expected_game_value = match options.solver {
+        Solver::Cfr => expected_game_value.map(|x| x / n_iterations as f64),
+        _ => expected_utility(&tree, &table.average_profile()),
+    };
+    save_strategies(&options, &tree, &table);
+    display_results(&options, expected_game_value, &tree, &table);
+
+    if options.pruning.is_some() && options.solver == Solver::Cfr {
+        // Without pruning every node below the deal is visited once per iteration
+        println!(
+            "nodes visited per iteration: {:.1} with pruning, {} without",
+            table.n_nodes_visited as f64 / n_iterations as f64,
+            tree.nodes.len() - 1
+        );
+    }
+
+    if options.purification_report {
+        purification_report(&tree, &table);
+    }
+
+    if options.info_set_report {
+        info_set_report(&tree, &table);
+    }
+
+    if !options.locks.is_empty() {
+        node_locking_report(&options, &tree, &table);
+    }
+
+    if let Some((source, model)) = &opponent {
+        opponent_report(&options, &tree, &table, source, model);
+        restricted_nash_report(&options, &tree, source, model);
+    }
+
+    if !options.baselines.is_empty() {
+        let profile = table.purified_profile(options.purification);
+        baseline_report(&options, &tree, &[("the trained strategy", profile)]);
+    }
+
+    if options.n_simulated_hands > 0 {
+        let mut rng = StdRng::seed_from_u64(options.seed);
+        let profile = table.purified_profile(options.purification);
+        let mean = simulate(&tree, &profile, options.n_simulated_hands, &mut rng);
+
+        println!(
+            "\n{} simulated hands with seed {}",
+            options.n_simulated_hands, options.seed
+        );
+        for (player, value) in mean.iter().enumerate() {
+            println!("player {} mean winnings: {}", player + 1, value);
+        }
+    }
+}
+
+// Algorithm used to train the strategies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Solver {
+    Cfr,
+    FictitiousPlay,
+    SequenceForm,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Solver, String> {
+        match s {
+            "cfr" => Ok(Solver::Cfr),
+            "fp" => Ok(Solver::FictitiousPlay),
+            "lp" => Ok(Solver::SequenceForm),
+            _ => Err(format!("unknown solver {}, expected cfr, fp or lp", s)),
+        }
+    }
+}
+
+// Command line options, e.g. `cargo run -p task2 -- --threads 4 --players 3 --ranks 5 --ante 1 --bet 2`
+//
+// `--solver fp --iterations 1000 --report 10` runs fictitious play instead of CFR and
+// prints the exploitability every 10 iterations, `--solver lp` solves the sequence-form
+// linear program exactly. `--purify argmax` cleans up the average strategies before
+// they are shown and `--purification-report` compares the options. `--info-set-report`
+// lists the CFR statistics of every information set. `--minimizer rm+` picks how CFR
+// turns the regrets into strategies and `--prune 10` skips the actions with a regret
+// below `--prune-threshold`, traversing the whole tree every 10 iterations.
+// `--lock "J rrc=0,1"` makes CFR always play the given strategy at an information set,
+// it can be repeated. `--opponent model.txt` or `--opponent-log hands.txt` reads an
+// opponent model and reports what a best response against it wins, `--rnr 0,0.5,1`
+// adds restricted Nash responses to it for each probability of following the model.
+// `--play` deals hands between you and the trained strategies instead of printing them.
+// `--simulate 10000 --seed 7` plays
+// 10000 hands between the purified strategies, the same seed replays the same hands.
+// `--save kuhn.txt` writes the purified strategies to a file and `--match a.txt
+// --against b.txt --deals 10000` plays two saved files against each other instead of
+// training. `--baselines` plays the trained strategies against every `Baseline` bot,
+// or the files given with `--strategy a.txt --strategy b.txt` without training,
+// `--baseline king --baseline strength:0.7` picks the bots instead.
+// `--tournament results` plays every pair of `--strategy` files and writes their
+// ratings, winnings and win rates to results-ratings.csv, results-winnings.md and so on
+pub struct Options {
+    pub n_threads: usize,
+    pub bench: bool,
+    pub config: KuhnConfig,
+    pub solver: Solver,
+    pub minimizer: RegretMinimizer,
+    pub pruning: Option<Pruning>,
+    pub locks: Vec<Lock>,
+    pub opponent: Option<OpponentSource>,
+    pub rnr: Vec<f64>,
+    pub n_iterations: usize,
+    pub report_every: usize,
+    pub purification: Purification,
+    pub purification_report: bool,
+    pub info_set_report: bool,
+    pub play: bool,
+    pub n_simulated_hands: usize,
+    pub seed: u64,
+    pub save: Option<String>,
+    pub matchup: Option<(String, String)>,
+    pub n_deals: usize,
+    pub baselines: Vec<Baseline>,
+    pub strategies: Vec<String>,
+    pub tournament: Option<String>,
+}
+
+impl Options {
+    pub fn from_args() -> Options {
+        let args: Vec<String> = std::env::args().collect();
+
+        let n_threads = arg_value::<usize>(&args, "--threads")
+            .filter(|&n| n > 0)
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+
+        let bench = args.iter().any(|arg| arg == "--bench");
+
+        // Without `--ranks` the deck has one card more than there are players
+        let default = KuhnConfig::default();
+        let n_players = arg_value(&args, "--players").unwrap_or(default.n_players);
+        let config = KuhnConfig::new(
+            n_players,
+            arg_value(&args, "--ranks").unwrap_or(n_players + 1),
+            arg_value(&args, "--ante").unwrap_or(default.ante),
+            arg_value(&args, "--bet").unwrap_or(default.bet),
+        )
+        .unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
+
+        let solver = arg_value::<String>(&args, "--solver")
+            .map_or(Ok(Solver::Cfr), |name| name.parse())
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        let minimizer = arg_value::<String>(&args, "--minimizer")
+            .map_or(Ok(RegretMinimizer::default()), |name| name.parse())
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        let pruning = arg_value::<usize>(&args, "--prune")
+            .filter(|&interval| interval > 0)
+            .map(|interval| Pruning {
+                threshold: arg_value(&args, "--prune-threshold")
+                    .unwrap_or(Pruning::default().threshold),
+                interval,
+            });
+
+        if let Some(Err(error)) = pruning.map(|pruning| pruning.check(minimizer)) {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+
+        let locks = arg_values::<String>(&args, "--lock")
+            .iter()
+            .map(|lock| lock.parse())
+            .collect::<Result<Vec<Lock>, String>>()
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        if !locks.is_empty() && solver != Solver::Cfr {
+            eprintln!("locking information sets needs --solver cfr");
+            process::exit(2);
+        }
+
+        let opponent = arg_value(&args, "--opponent")
+            .map(OpponentSource::Table)
+            .or_else(|| arg_value(&args, "--opponent-log").map(OpponentSource::Log));
+
+        let rnr = arg_value::<String>(&args, "--rnr")
+            .map_or(Ok(Vec::new()), |list| {
+                list.split(',')
+                    .map(|p| {
+                        p.trim()
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|p| (0.0..=1.0).contains(p))
+                    })
+                    .collect::<Option<Vec<f64>>>()
+                    .ok_or_else(|| {
+                        format!("expected probabilities between 0 and 1 in --rnr {}", list)
+                    })
+            })
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        if !rnr.is_empty() && opponent.is_none() {
+            eprintln!("the restricted Nash response needs --opponent or --opponent-log");
+            process::exit(2);
+        }
+
+        let matchup = arg_value::<String>(&args, "--match").map(|a| {
+            let b = arg_value(&args, "--against").unwrap_or_else(|| {
+                eprintln!("--match needs a second strategy file with --against");
+                process::exit(2);
+            });
+            (a, b)
+        });
+
+        let strategies: Vec<String> = arg_values(&args, "--strategy");
+        let tournament = arg_value::<String>(&args, "--tournament");
+
+        if tournament.is_some() && strategies.len() < 2 {
+            eprintln!("the tournament needs at least two --strategy files");
+            process::exit(2);
+        }
+
+        let mut baselines = arg_values::<String>(&args, "--baseline")
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<Baseline>, String>>()
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+        if baselines.is_empty() && args.iter().any(|arg| arg == "--baselines") {
+            baselines = BASELINES.to_vec();
+        }
+
+        let purification = arg_value::<String>(&args, "--purify")
+            .map_or(Ok(Purification::default()), |name| name.parse())
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
 
-    let (pr_1_factor, pr_2_factor) = if is_player_1 {
-        (pr_2, pr_c)
-    } else {
-        (pr_1, pr_c)
+        Options {
+            n_threads,
+            bench,
+            config,
+            solver,
+            minimizer,
+            pruning,
+            locks,
+            opponent,
+            rnr,
+            n_iterations: arg_value(&args, "--iterations").unwrap_or(10000),
+            report_every: arg_value(&args, "--report").unwrap_or(0),
+            purification,
+            purification_report: args.iter().any(|arg| arg == "--purification-report"),
+            info_set_report: args.iter().any(|arg| arg == "--info-set-report"),
+            play: args.iter().any(|arg| arg == "--play"),
+            n_simulated_hands: arg_value(&args, "--simulate").unwrap_or(0),
+            seed: arg_value(&args, "--seed").unwrap_or(0),
+            save: arg_value(&args, "--save"),
+            matchup,
+            n_deals: arg_value(&args, "--deals")
+                .filter(|&n| n > 0)
+                .unwrap_or(10000),
+            baselines,
+            strategies,
+            tournament,
+        }
+    }
+}
+
+// Parse the value following a flag, e.g. `4` in `--threads 4`
+//
+// A flag without a value or with one that does not parse exits with status 2
+fn arg_value<T: FromStr>(args: &[String], name: &str) -> Option<T>
+where
+    T::Err: Display,
+{
+    let i = args.iter().position(|arg| arg == name)?;
+    let Some(value) = args.get(i + 1) else {
+        eprintln!("{} needs a value", name);
+        process::exit(2);
+    };
+    Some(parse_arg(name, value))
+}
+
+// Parse the values following every occurrence of a flag, e.g. `--lock A --lock B`
+fn arg_values<T: FromStr>(args: &[String], name: &str) -> Vec<T>
+where
+    T::Err: Display,
+{
+    if args.last().is_some_and(|arg| arg == name) {
+        eprintln!("{} needs a value", name);
+        process::exit(2);
+    }
+    args.windows(2)
+        .filter(|pair| pair[0] == name)
+        .map(|pair| parse_arg(name, &pair[1]))
+        .collect()
+}
+
+fn parse_arg<T: FromStr>(name: &str, value: &str) -> T
+where
+    T::Err: Display,
+{
+    value.parse().unwrap_or_else(|error| {
+        eprintln!("invalid value '{}' for {}: {}", value, name, error);
+        process::exit(2);
+    })
+}
+
+// The CFR (Counter Factual Regret Minimization) algorithm, returns the utility of every player
+//
+// `reach` holds the probability of every player playing to `node`, `pr_c` the one of
+// chance. Strategies are only read from `table`, all updates go into `buffer` so that
+// several deals can be traversed at the same time
+pub fn cfr(
+    tree: &GameTree,
+    table: &InfoSetTable,
+    buffer: &mut RegretBuffer,
+    node: usize,
+    reach: [f64; MAX_PLAYERS],
+    pr_c: f64,
+) -> Utility {
+    buffer.n_nodes_visited += 1;
+
+    let (player, info_set, children) = match &tree.nodes[node] {
+        Node::Terminal { utility } => return *utility,
+        Node::Chance { outcomes } => {
+            let mut util = [0.0; MAX_PLAYERS];
+            for &(pr, child) in outcomes {
+                let child_util = cfr(tree, table, buffer, child, reach, pr_c * pr);
+                add_scaled(&mut util, &child_util, pr);
+            }
+            return util;
+        }
+        Node::Decision {
+            player,
+            info_set,
+            children,
+        } => (*player, *info_set, children),
     };
 
-    info_set
-        .regret_sum
+    let strategy = table.strategy(info_set);
+    let pruned = table.pruned_actions(info_set);
+
+    let mut action_utils = [[0.0; MAX_PLAYERS]; N_ACTIONS];
+
+    for (i, &child) in children.iter().enumerate() {
+        // A pruned action is never played, so the utility of the node does not need it
+        if pruned[i] {
+            continue;
+        }
+
+        let mut child_reach = reach;
+        child_reach[player] *= strategy[i];
+        action_utils[i] = cfr(tree, table, buffer, child, child_reach, pr_c);
+    }
+
+    // The node is worth the strategy renormalised over the actions that were traversed
+    let traversed_pr = (0..N_ACTIONS)
+        .filter(|&i| !pruned[i])
+        .map(|i| strategy[i])
+        .sum::<f64>();
+    let mut util = [0.0; MAX_PLAYERS];
+    for i in (0..N_ACTIONS).filter(|&i| !pruned[i]) {
+        add_scaled(&mut util, &action_utils[i], strategy[i] / traversed_pr);
+    }
+
+    let mut regrets = action_utils.map(|action_util| action_util[player] - util[player]);
+    for (regret, _) in regrets
+        .iter_mut()
+        .zip(pruned)
+        .filter(|&(_, is_pruned)| is_pruned)
+    {
+        *regret = 0.0;
+    }
+
+    // The regrets are weighted by the probability of the other players and chance
+    // reaching this node, the unused seats keep a reach of 1
+    let counterfactual_pr = pr_c
+        * reach
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != player)
+            .map(|(_, &pr)| pr)
+            .product::<f64>();
+
+    buffer.reach_pr[info_set] += reach[player];
+
+    buffer.regret[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS]
         .iter_mut()
         .zip(regrets.iter())
-        .for_each(|(a, &b)| *a += pr_1_factor * pr_2_factor * b);
+        .for_each(|(a, &b)| *a += counterfactual_pr * b);
 
-    i_map.insert(key, info_set);
+    // A pruned action has no value in this iteration
+    for i in (0..N_ACTIONS).filter(|&i| !pruned[i]) {
+        buffer.action_value[info_set * N_ACTIONS + i] +=
+            counterfactual_pr * action_utils[i][player];
+    }
 
     util
 }
 
-impl fmt::Display for InformationSet {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} {:?}", self.key, self.get_average_strategy())
+// Add `scale` times `other` to `utility`
+pub fn add_scaled(utility: &mut Utility, other: &Utility, scale: f64) {
+    utility
+        .iter_mut()
+        .zip(other.iter())
+        .for_each(|(a, &b)| *a += scale * b);
+}
+
+// Check if we have reached the terminal history
+//
+// The game ends when every player checks or when every other player has called
+// or folded the bet, there is a single bet
+pub fn is_terminal(config: &KuhnConfig, history: &str) -> bool {
+    let actions = &history[2..];
+
+    match actions.find('b') {
+        Some(bet) => actions.len() == bet + config.n_players,
+        None => actions.len() == config.n_players,
     }
 }
 
-// Determine if we are at the natural moves (random chance nodes)
-fn is_chance_node(history: &str) -> bool {
-    history.is_empty()
+// Whether the player to act has a bet to call, there is a single bet in the game
+pub fn is_facing_bet(history: &str) -> bool {
+    history[2..].contains('b')
 }
 
-// Start the CFR algorithm by iterating through every combination of chance nodes
-fn chance_util(i_map: &mut HashMap<String, InformationSet>) -> f64 {
-    let mut expected_value = 0.0;
-    let n_possibilities = 6;
-    for i in 0..N_CARDS {
-        for j in 0..N_CARDS {
-            if i != j {
-                expected_value += cfr(
-                    i_map,
-                    "rr",
-                    i as isize,
-                    j as isize,
-                    1.0,
-                    1.0,
-                    1.0 / n_possibilities as f64,
-                );
+// Calculate the terminal utility of every player
+pub fn terminal_util(config: &KuhnConfig, history: &str, cards: &[isize]) -> Utility {
+    let actions = &history[2..];
+    let mut contributions = [0.0; MAX_PLAYERS];
+    let mut folded = [false; MAX_PLAYERS];
+
+    contributions[..config.n_players].fill(config.ante);
+
+    if let Some(bet) = actions.find('b') {
+        // Checking after the bet folds, the players who fold lose what they put in
+        for (i, op) in actions.chars().enumerate().skip(bet) {
+            let player = i % config.n_players;
+
+            if op == 'b' {
+                contributions[player] += config.bet;
+            } else {
+                folded[player] = true;
             }
         }
     }
-    expected_value / n_possibilities as f64
+
+    // The highest card among the players left takes the pot
+    let winner = (0..config.n_players)
+        .filter(|&player| !folded[player])
+        .max_by_key(|&player| cards[player])
+        .expect("the bettor never folds");
+
+    let mut utility = contributions.map(|x| -x);
+    utility[winner] += contributions.iter().sum::<f64>();
+    utility
 }
 
-// Check if we have reached the terminal history
-fn is_terminal(history: &str) -> bool {
-    let possibilities = ["rrcc", "rrcbc", "rrcbb", "rrbc", "rrbb"];
-    possibilities.contains(&history)
+// Build the dictionary key of an information set
+pub fn get_info_set_key(config: &KuhnConfig, card: isize, history: &str) -> String {
+    format!("{} {}", config.card_str(card), history)
 }
 
-// Calculate the terminal utility
-fn terminal_util(history: &str, card_1: isize, card_2: isize) -> f64 {
-    let n = history.len();
-    let card_player = if n % 2 == 0 { card_1 } else { card_2 };
-    let card_opponent = if n % 2 == 0 { card_2 } else { card_1 };
-    match history {
-        "rrcbc" | "rrbc" => 1.0,
-        "rrcc" => {
-            if card_player > card_opponent {
-                1.0
-            } else {
-                -1.0
-            }
+fn display_results(options: &Options, ev: Utility, tree: &GameTree, table: &InfoSetTable) {
+    let config = &options.config;
+    let profile = table.purified_profile(options.purification);
+
+    println!(
+        "kuhn poker with {} players, {} ranks, ante {} and bet {}",
+        config.n_players, config.n_ranks, config.ante, config.bet
+    );
+    for (player, value) in ev.iter().take(config.n_players).enumerate() {
+        println!("player {} expected value: {}", player + 1, value);
+    }
+    println!(
+        "exploitability with purification {}: {}",
+        options.purification,
+        exploitability(tree, &profile)
+    );
+
+    let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
+
+    items.sort_by(|a, b| a.1.key.cmp(&b.1.key));
+
+    for player in 0..config.n_players {
+        println!("\nplayer {} strategies:", player + 1);
+        for (i, info_set) in items
+            .iter()
+            .filter(|(_, info_set)| info_set.player == player)
+        {
+            println!("{} {:?}", info_set.key, profile[*i]);
         }
-        "rrcbb" | "rrbb" => {
-            if card_player > card_opponent {
-                2.0
-            } else {
-                -2.0
-            }
+    }
+}
+
+// Write the purified average strategies to the `--save` file
+fn save_strategies(options: &Options, tree: &GameTree, table: &InfoSetTable) {
+    if let Some(path) = &options.save {
+        let profile = table.purified_profile(options.purification);
+        if let Err(error) = strategy_file::save(tree, &profile, path) {
+            eprintln!("{}", error);
+            process::exit(2);
         }
-        _ => 0.0,
     }
 }
 
-// Retrieve information set from dictionary
-fn get_info_set(
-    i_map: &mut HashMap<String, InformationSet>,
-    card: isize,
-    history: &str,
-) -> (String, InformationSet) {
-    let key = format!("{} {}", card_str(card as usize), history);
+// Play the strategy file `a` against `b` with duplicate dealing and report what `a`
+// wins per hand
+fn match_session(options: &Options, tree: &GameTree, a: &str, b: &str) {
+    let [profile_a, profile_b] = [a, b].map(|path| {
+        strategy_file::load(tree, path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        })
+    });
 
-    let info_set = i_map
-        .remove(&key)
-        .unwrap_or_else(|| InformationSet::new(&key));
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let samples = duplicate_match(tree, &profile_a, &profile_b, options.n_deals, &mut rng);
+    let estimate = Estimate::new(&samples);
+    let (low, high) = estimate.confidence_interval();
 
-    (key, info_set)
+    println!(
+        "{} against {}: {} duplicate deals ({} hands) with seed {}",
+        a,
+        b,
+        options.n_deals,
+        options.n_deals * tree.n_players,
+        options.seed
+    );
+    println!(
+        "{} mean winnings per hand: {:.6} (standard error {:.6})",
+        a, estimate.mean, estimate.std_error
+    );
+    println!("95% confidence interval: [{:.6}, {:.6}]", low, high);
 }
 
-fn card_str(card: usize) -> &'static str {
-    match card {
-        0 => "J",
-        1 => "Q",
-        _ => "K",
+// Round robin between the `--strategy` files, the ratings and the matrix of winnings
+// are printed and written as CSV and Markdown next to `prefix`
+fn tournament_session(options: &Options, tree: &GameTree, prefix: &str) {
+    let entries: Vec<_> = options
+        .strategies
+        .iter()
+        .map(|path| {
+            let profile = strategy_file::load(tree, path).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+            (path.clone(), profile)
+        })
+        .collect();
+
+    let tournament = round_robin(tree, &entries, options.n_deals, options.seed);
+
+    println!(
+        "round robin between {} strategies, {} duplicate deals per match with seed {}\n",
+        entries.len(),
+        options.n_deals,
+        options.seed
+    );
+    print!("{}", tournament.ratings_markdown());
+    println!("\nwinnings per hand of the row against the column:\n");
+    print!("{}", tournament.winnings_markdown());
+    println!("\nshare of the deals the row wins against the column:\n");
+    print!("{}", tournament.win_rates_markdown());
+
+    for (suffix, text) in [
+        ("ratings.csv", tournament.ratings_csv()),
+        ("ratings.md", tournament.ratings_markdown()),
+        ("winnings.csv", tournament.winnings_csv()),
+        ("winnings.md", tournament.winnings_markdown()),
+        ("win-rates.csv", tournament.win_rates_csv()),
+        ("win-rates.md", tournament.win_rates_markdown()),
+    ] {
+        let path = format!("{}-{}", prefix, suffix);
+        if let Err(error) = std::fs::write(&path, text) {
+            eprintln!("cannot write {}: {}", path, error);
+            process::exit(2);
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct InformationSet {
-    pub key: String,
-    pub regret_sum: [f64; N_ACTIONS],
-    pub strategy_sum: [f64; N_ACTIONS],
-    pub strategy: [f64; N_ACTIONS],
-    pub reach_pr: f64,
-    pub reach_pr_sum: f64,
+// Every pair of `entries` plays a duplicate match on the deals of `seed`
+fn round_robin(
+    tree: &GameTree,
+    entries: &[(String, Vec<[f64; N_ACTIONS]>)],
+    n_deals: usize,
+    seed: u64,
+) -> Tournament {
+    let names = entries.iter().map(|(name, _)| name.clone()).collect();
+
+    Tournament::new(names, |i, j| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        duplicate_match(tree, &entries[i].1, &entries[j].1, n_deals, &mut rng)
+    })
 }
 
-impl InformationSet {
-    pub fn new(key: &str) -> InformationSet {
-        InformationSet {
-            key: key.to_string(),
-            regret_sum: [0.0; N_ACTIONS],
-            strategy_sum: [0.0; N_ACTIONS],
-            strategy: [1.0 / N_ACTIONS as f64; N_ACTIONS],
-            reach_pr: 0.0,
-            reach_pr_sum: 0.0,
+// Mean winnings per hand of every agent against each baseline bot over duplicate
+// deals, with the share of the deals it wins. Every match uses the same seed so the
+// agents face the same cards
+fn baseline_report(options: &Options, tree: &GameTree, agents: &[(&str, Vec<[f64; N_ACTIONS]>)]) {
+    for (name, profile) in agents {
+        println!(
+            "\n{} against the baselines, {} duplicate deals with seed {}:",
+            name, options.n_deals, options.seed
+        );
+        println!(
+            "{:<14} {:>10} {:>22} {:>10}",
+            "baseline", "winnings", "95% interval", "deals won"
+        );
+
+        for baseline in &options.baselines {
+            let mut rng = StdRng::seed_from_u64(options.seed);
+            let samples = duplicate_match(
+                tree,
+                profile,
+                &baseline.profile(tree),
+                options.n_deals,
+                &mut rng,
+            );
+            let estimate = Estimate::new(&samples);
+            let (low, high) = estimate.confidence_interval();
+            let n_won = samples.iter().filter(|&&x| x > 0.0).count();
+
+            println!(
+                "{:<14} {:>10.4} {:>22} {:>9.1}%",
+                baseline.to_string(),
+                estimate.mean,
+                format!("[{:.4}, {:.4}]", low, high),
+                100.0 * n_won as f64 / samples.len() as f64
+            );
         }
     }
+}
+
+// Play against the average strategies after `--purification` in the terminal, the
+// deals and the actions of the bots come from `--seed`
+fn play_session(options: &Options, tree: &GameTree, table: &InfoSetTable) {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let profile = table.purified_profile(options.purification);
+
+    match play(
+        tree,
+        &profile,
+        &mut rng,
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+    ) {
+        Ok(score) => println!("\nfinal score: {:+}", score),
+        Err(error) => eprintln!("{}", error),
+    }
+}
 
-    pub fn next_strategy(&mut self) {
-        self.strategy_sum
-            .iter_mut()
-            .zip(self.strategy.iter())
-            .for_each(|(a, &b)| *a += self.reach_pr * b);
+// Exploitability of the average strategies after each purification and how much the
+// purification adds to it
+fn purification_report(tree: &GameTree, table: &InfoSetTable) {
+    let profile = table.average_profile();
+    let base = exploitability(tree, &profile);
 
-        self.strategy = self.calc_strategy();
-        self.reach_pr_sum += self.reach_pr;
-        self.reach_pr = 0.0;
+    println!("\npurification exploitability:");
+    for purification in [
+        Purification::None,
+        Purification::Threshold(0.001),
+        Purification::Threshold(0.01),
+        Purification::Threshold(0.05),
+        Purification::Threshold(0.1),
+        Purification::Argmax,
+    ] {
+        let purified: Vec<[f64; N_ACTIONS]> = profile
+            .iter()
+            .map(|&strategy| purification.apply(strategy))
+            .collect();
+        let value = exploitability(tree, &purified);
+
+        println!(
+            "{:<16} {:.6} ({:+.6})",
+            purification.to_string(),
+            value,
+            value - base
+        );
     }
+}
+
+// Reach probability, counterfactual value of every action, cumulative regret and
+// average strategy of each information set, the most reached ones first
+//
+// `reach` is the probability of the set under the average strategies and `own` the
+// reach probability of its player summed over its histories, `reach_pr_sum`. Both
+// `own` and the values are averaged over the CFR iterations, the values are weighted
+// by how likely the other players and chance reach the set
+fn info_set_report(tree: &GameTree, table: &InfoSetTable) {
+    if table.n_iterations == 0 {
+        eprintln!("the information set report needs --solver cfr");
+        return;
+    }
+
+    let n_iterations = table.n_iterations as f64;
+    let reach = reach_probabilities(tree, &table.average_profile());
+    let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
 
-    fn calc_strategy(&self) -> [f64; N_ACTIONS] {
-        let strategy = self.make_positive(self.regret_sum);
+    items.sort_by(|a, b| {
+        reach[b.0]
+            .total_cmp(&reach[a.0])
+            .then_with(|| a.1.key.cmp(&b.1.key))
+    });
 
-        let total = strategy.iter().sum::<f64>();
+    println!("\ninformation set report:");
+    for (i, info_set) in items {
+        let range = i * N_ACTIONS..(i + 1) * N_ACTIONS;
+        let values: Vec<f64> = table.action_value_sum[range.clone()]
+            .iter()
+            .map(|x| x / n_iterations)
+            .collect();
 
-        if total > 0.0 {
-            strategy.map(|x| x / total)
-        } else {
-            [1.0 / N_ACTIONS as f64; N_ACTIONS]
+        println!(
+            "{} reach {:.4} own {:.4} value [{}] regret [{}] strategy [{}]",
+            info_set.key,
+            reach[i],
+            table.reach_pr_sum[i] / n_iterations,
+            format_actions(&values),
+            format_actions(&table.regret_sum[range]),
+            format_actions(&table.get_average_strategy(i))
+        );
+    }
+}
+
+// Expected value of every player with the locked information sets against a CFR run
+// of the same length without them, e.g. how much player 2 wins when player 1 always
+// calls with the Jack
+fn node_locking_report(options: &Options, tree: &GameTree, table: &InfoSetTable) {
+    let mut unlocked = InfoSetTable::new(tree.info_sets.len())
+        .with_minimizer(options.minimizer)
+        .with_pruning(options.pruning);
+
+    with_workers(tree, options.n_threads, |workers| {
+        for _ in 0..options.n_iterations {
+            workers.chance_util(&mut unlocked);
+            unlocked.next_strategy();
         }
+    });
+
+    let before = expected_utility(tree, &unlocked.average_profile());
+    let after = expected_utility(tree, &table.average_profile());
+
+    println!("\nnode locking report:");
+    for lock in &options.locks {
+        println!("locked {} [{}]", lock.key, format_actions(&lock.strategy));
+    }
+    for player in 0..tree.n_players {
+        let has_locks = tree
+            .info_sets
+            .iter()
+            .zip(table.locked.iter())
+            .any(|(info_set, &locked)| locked && info_set.player == player);
+
+        println!(
+            "player {}{} expected value: {:.6} unlocked, {:.6} locked ({:+.6})",
+            player + 1,
+            if has_locks { " (locked)" } else { "" },
+            before[player],
+            after[player],
+            after[player] - before[player]
+        );
     }
+}
+
+// What the best response of every player left out of the opponent model wins against
+// it, compared with the shown equilibrium strategy, followed by the action the best
+// response picks at each information set
+fn opponent_report(
+    options: &Options,
+    tree: &GameTree,
+    table: &InfoSetTable,
+    source: &OpponentSource,
+    model: &OpponentModel,
+) {
+    let equilibrium = table.purified_profile(options.purification);
 
-    fn get_average_strategy(&self) -> [f64; N_ACTIONS] {
-        let strategy = self.strategy_sum.map(|x| x / self.reach_pr_sum);
+    println!(
+        "\nexploiting the opponent {} ({} information sets):",
+        source,
+        model.strategies.len()
+    );
+    for player in model.exploiting_players(tree) {
+        let exploitation = exploit(tree, model, &equilibrium, player);
 
-        let strategy = strategy.map(|x| if x < 0.001 { 0.0 } else { x });
+        println!(
+            "player {} best response {:.6}, equilibrium {:.6} ({:+.6})",
+            player + 1,
+            exploitation.best_response,
+            exploitation.equilibrium,
+            exploitation.best_response - exploitation.equilibrium
+        );
 
-        let total = strategy.iter().sum::<f64>();
+        let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
+        items.sort_by(|a, b| a.1.key.cmp(&b.1.key));
 
-        if total > 0.0 {
-            strategy.map(|x| x / total)
-        } else {
-            [1.0 / N_ACTIONS as f64; N_ACTIONS]
+        for (i, info_set) in items {
+            if let Some(action) = exploitation.actions[i] {
+                println!("{} {}", info_set.key, CHANCE_ACTIONS[action]);
+            }
         }
     }
+}
 
-    fn make_positive(&self, x: [f64; N_ACTIONS]) -> [f64; N_ACTIONS] {
-        x.map(|val| val.max(0.0))
+// Restricted Nash response of the player left out of the opponent model for every
+// `--rnr` probability: what it wins against the model, what it wins against a best
+// response and how much less that is than the value of the game
+fn restricted_nash_report(
+    options: &Options,
+    tree: &GameTree,
+    source: &OpponentSource,
+    model: &OpponentModel,
+) {
+    if options.rnr.is_empty() {
+        return;
+    }
+
+    let exploiter = match model.exploiting_players(tree)[..] {
+        [exploiter] if tree.n_players == 2 => exploiter,
+        _ => {
+            eprintln!("the restricted Nash response needs a model of one of two players");
+            return;
+        }
+    };
+
+    let value = match sequence_form::solve(tree) {
+        Ok((value, _)) => value,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
+    let game_value = if exploiter == 0 { value } else { -value };
+
+    println!(
+        "\nrestricted Nash response of player {} against the opponent {}:",
+        exploiter + 1,
+        source
+    );
+    println!(
+        "{:<6} {:>12} {:>12} {:>14}",
+        "p", "vs model", "worst case", "exploitability"
+    );
+    for &p in &options.rnr {
+        let restricted = restricted_tree(tree, model, p);
+        let mut table = InfoSetTable::new(restricted.info_sets.len())
+            .with_minimizer(options.minimizer)
+            .with_pruning(options.pruning);
+        lock_model(&mut table, tree, model);
+
+        with_workers(&restricted, options.n_threads, |workers| {
+            for _ in 0..options.n_iterations {
+                workers.chance_util(&mut table);
+                table.next_strategy();
+            }
+        });
+
+        // The copies of the modelled information sets come after the original ones
+        let profile = &table.average_profile()[..tree.info_sets.len()];
+        let point = evaluate(tree, model, profile, exploiter);
+
+        println!(
+            "{:<6.2} {:>12.6} {:>12.6} {:>14.6}",
+            p,
+            point.against_model,
+            point.worst_case,
+            game_value - point.worst_case
+        );
     }
 }
 
-fn display_results(ev: f64, i_map: &HashMap<String, InformationSet>) {
-    println!("player 1 expected value: {}", ev);
-    println!("player 2 expected value: {}", -1.0 * ev);
+// Label every value with its action, e.g. `c 0.2500, b 0.7500`
+fn format_actions(values: &[f64]) -> String {
+    CHANCE_ACTIONS
+        .iter()
+        .zip(values.iter())
+        .map(|(action, x)| format!("{} {:.4}", action, x))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    let mut items = i_map.iter().collect::<Vec<_>>();
+// Time the tree construction and the CFR iterations, e.g. `cargo run --release -p task2 -- --bench`
+fn bench(options: &Options) {
+    let n_iterations = 10000;
+
+    let start = Instant::now();
+    let tree = GameTree::new(&options.config);
+    let build_time = start.elapsed();
+
+    println!(
+        "kuhn: {} nodes, {} information sets, built in {:?}",
+        tree.nodes.len(),
+        tree.info_sets.len(),
+        build_time
+    );
+
+    for pruning in [None, Some(options.pruning.unwrap_or_default())] {
+        let mut table = InfoSetTable::new(tree.info_sets.len()).with_pruning(pruning);
+
+        let start = Instant::now();
+        let n_threads = with_workers(&tree, options.n_threads, |workers| {
+            for _ in 0..n_iterations {
+                workers.chance_util(&mut table);
+                table.next_strategy();
+            }
+            workers.n_threads()
+        });
+        let train_time = start.elapsed();
+
+        println!(
+            "kuhn{}: {} iterations on {} threads in {:?} ({:.2} µs/iteration, {:.1} nodes/iteration)",
+            if pruning.is_some() { " with pruning" } else { "" },
+            n_iterations,
+            n_threads,
+            train_time,
+            train_time.as_secs_f64() * 1e6 / n_iterations as f64,
+            table.n_nodes_visited as f64 / n_iterations as f64
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use best_response::best_response;
+    use common::simulate::seat_profile;
+    use workers::Workers;
+
+    fn train(config: &KuhnConfig, n_iterations: usize) -> (GameTree, InfoSetTable) {
+        let tree = GameTree::new(config);
+        let mut table = InfoSetTable::new(tree.info_sets.len());
+
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..n_iterations {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+
+        (tree, table)
+    }
+
+    // Probability of betting, or of calling when facing a bet
+    fn bet_pr(tree: &GameTree, table: &InfoSetTable, key: &str) -> f64 {
+        let bet = CHANCE_ACTIONS.iter().position(|&a| a == "b").unwrap();
+        let i = tree.info_sets.iter().position(|x| x.key == key).unwrap();
+        table.get_average_strategy(i)[bet]
+    }
+
+    #[test]
+    fn game_value_is_minus_one_eighteenth() {
+        let (tree, table) = train(&KuhnConfig::default(), 10000);
+        let utility = expected_utility(&tree, &table.average_profile());
+
+        assert!((utility[0] + 1.0 / 18.0).abs() < 2e-3, "{:?}", utility);
+        assert!((utility[1] - 1.0 / 18.0).abs() < 2e-3, "{:?}", utility);
+    }
 
-    items.sort_by(|a, b| a.0.cmp(b.0));
+    #[test]
+    fn strategies_are_in_the_equilibrium_family() {
+        let (tree, table) = train(&KuhnConfig::default(), 10000);
+        let pr = |key| bet_pr(&tree, &table, key);
+        let tolerance = 0.02;
 
-    let (p1_items, p2_items) = items
-        .into_iter()
-        .partition::<Vec<_>, _>(|(k, _)| k.len() % 2 == 0);
+        // Player 1 bluffs with J at some rate alpha in [0, 1/3], which fixes the rest
+        let alpha = pr("J rr");
+        assert!((-tolerance..=1.0 / 3.0 + tolerance).contains(&alpha));
+        assert!((pr("Q rr") - 0.0).abs() < tolerance);
+        assert!((pr("K rr") - 3.0 * alpha).abs() < tolerance);
+        assert!((pr("J rrcb") - 0.0).abs() < tolerance);
+        assert!((pr("Q rrcb") - (alpha + 1.0 / 3.0)).abs() < tolerance);
+        assert!((pr("K rrcb") - 1.0).abs() < tolerance);
 
-    println!("\nplayer 1 strategies:");
-    for (_, v) in p1_items {
-        println!("{}", v);
+        // Player 2 has a single equilibrium strategy
+        assert!((pr("J rrb") - 0.0).abs() < tolerance);
+        assert!((pr("Q rrb") - 1.0 / 3.0).abs() < tolerance);
+        assert!((pr("K rrb") - 1.0).abs() < tolerance);
+        assert!((pr("J rrc") - 1.0 / 3.0).abs() < tolerance);
+        assert!((pr("Q rrc") - 0.0).abs() < tolerance);
+        assert!((pr("K rrc") - 1.0).abs() < tolerance);
     }
 
-    println!("\nplayer 2 strategies:");
-    for (_, v) in p2_items {
-        println!("{}", v);
+    #[test]
+    fn exploitability_goes_down() {
+        let config = KuhnConfig::default();
+        let (tree, table) = train(&config, 100);
+        let early = exploitability(&tree, &table.average_profile());
+        let (tree, table) = train(&config, 10000);
+        let late = exploitability(&tree, &table.average_profile());
+
+        assert!(late < early, "{} >= {}", late, early);
+        assert!(late < 5e-3, "{}", late);
+    }
+
+    #[test]
+    fn every_minimizer_converges() {
+        let tree = GameTree::new(&KuhnConfig::default());
+
+        for minimizer in ["rm", "rm+", "prm+", "hedge"] {
+            let minimizer: RegretMinimizer = minimizer.parse().unwrap();
+            let mut table = InfoSetTable::new(tree.info_sets.len()).with_minimizer(minimizer);
+
+            let mut workers = Workers::serial(&tree);
+            for _ in 0..2000 {
+                workers.chance_util(&mut table);
+                table.next_strategy();
+            }
+
+            let exploitability = exploitability(&tree, &table.average_profile());
+            assert!(exploitability < 0.02, "{}: {}", minimizer, exploitability);
+
+            if minimizer != RegretMinimizer::RegretMatching
+                && minimizer != RegretMinimizer::Hedge(1.0)
+            {
+                assert!(table.regret_sum.iter().all(|&x| x >= 0.0), "{}", minimizer);
+            }
+        }
+    }
+
+    #[test]
+    fn pruning_skips_nodes_and_still_converges() {
+        let tree = GameTree::new(&KuhnConfig::new(2, 5, 1.0, 2.0).unwrap());
+        let mut table =
+            InfoSetTable::new(tree.info_sets.len()).with_pruning(Some(Pruning::default()));
+
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..10000 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+
+        let nodes_per_iteration = table.n_nodes_visited as f64 / 10000.0;
+        assert!(nodes_per_iteration < 0.75 * (tree.nodes.len() - 1) as f64);
+
+        let exploitability = exploitability(&tree, &table.average_profile());
+        assert!(exploitability < 5e-3, "{}", exploitability);
+    }
+
+    #[test]
+    fn locked_information_sets_keep_their_strategy() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let lock: Lock = "J rr=0,1".parse().unwrap();
+        let info_set = lock.info_set(&tree).unwrap();
+
+        let mut table = InfoSetTable::new(tree.info_sets.len());
+        table.lock(info_set, &lock.strategy);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..2000 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+
+        let profile = table.average_profile();
+        assert_eq!(table.strategy(info_set), lock.strategy);
+        assert!((profile[info_set][1] - 1.0).abs() < 1e-12);
+
+        // Always bluffing with the Jack hands player 2 more than the game value
+        let utility = expected_utility(&tree, &profile);
+        assert!(utility[1] > 1.0 / 18.0 + 0.1, "{}", utility[1]);
+    }
+
+    #[test]
+    fn best_response_exploits_an_opponent_who_always_bets() {
+        let (tree, table) = train(&KuhnConfig::default(), 2000);
+        let model = OpponentModel::from_table(&tree, "J rr=0,1\nQ rr=0,1\nK rr=0,1").unwrap();
+        let equilibrium = table.average_profile();
+
+        assert_eq!(model.exploiting_players(&tree), vec![1]);
+        let exploitation = exploit(&tree, &model, &equilibrium, 1);
+
+        // Calling with the Queen and the King against a bet that is always made wins a third
+        assert!((exploitation.best_response - 1.0 / 3.0).abs() < 1e-12);
+        assert!(exploitation.best_response > exploitation.equilibrium + 0.1);
+    }
+
+    #[test]
+    fn restricted_nash_responses_trade_safety_for_exploitation() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let model = OpponentModel::from_table(&tree, "J rr=0,1\nQ rr=0,1\nK rr=0,1").unwrap();
+
+        let points: Vec<_> = [0.0, 0.5, 1.0]
+            .iter()
+            .map(|&p| {
+                let restricted = restricted_tree(&tree, &model, p);
+                let mut table = InfoSetTable::new(restricted.info_sets.len());
+                lock_model(&mut table, &tree, &model);
+
+                let mut workers = Workers::serial(&restricted);
+                for _ in 0..2000 {
+                    workers.chance_util(&mut table);
+                    table.next_strategy();
+                }
+                evaluate(&tree, &model, &table.average_profile(), 1)
+            })
+            .collect();
+
+        // Without the model it is an equilibrium, following the model always gives the best response
+        assert!((points[0].worst_case - 1.0 / 18.0).abs() < 1e-2);
+        assert!((points[2].against_model - 1.0 / 3.0).abs() < 1e-2);
+        assert!(points.windows(2).all(|pair| {
+            pair[0].against_model <= pair[1].against_model + 1e-3
+                && pair[0].worst_case >= pair[1].worst_case - 1e-3
+        }));
+    }
+
+    #[test]
+    fn best_response_never_loses_to_the_profile() {
+        let (tree, table) = train(&KuhnConfig::default(), 1000);
+        let profile = table.average_profile();
+        let utility = expected_utility(&tree, &profile);
+
+        for (player, &value) in utility.iter().take(tree.n_players).enumerate() {
+            assert!(best_response(&tree, &profile, player).value >= value - 1e-12);
+        }
+    }
+
+    #[test]
+    fn fictitious_play_alternates_the_responses() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let mut table = InfoSetTable::new(tree.info_sets.len());
+        fictitious_play(&tree, &mut table);
+
+        // Player 2 responds to the average that already holds the response of player 1
+        let profile = table.average_profile();
+        let response = best_response(&tree, &profile, 1);
+        for (i, action) in response.actions.iter().enumerate() {
+            if let Some(action) = *action {
+                if table.reach_pr_sum[i] > 0.0 {
+                    assert_eq!(profile[i][action], 1.0, "{}", i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fictitious_play_converges() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let mut table = InfoSetTable::new(tree.info_sets.len());
+        let mut exploitabilities = Vec::new();
+
+        for _ in 0..1000 {
+            fictitious_play(&tree, &mut table);
+            exploitabilities.push(exploitability(&tree, &table.average_profile()));
+        }
+
+        assert!(exploitabilities[999] < exploitabilities[9]);
+        assert!(exploitabilities[999] < 0.02, "{}", exploitabilities[999]);
+
+        let utility = expected_utility(&tree, &table.average_profile());
+        assert!((utility[0] + 1.0 / 18.0).abs() < 0.01, "{:?}", utility);
+    }
+
+    #[test]
+    fn sequence_form_is_exact() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let (value, profile) = sequence_form::solve(&tree).unwrap();
+
+        assert!((value + 1.0 / 18.0).abs() < 1e-9, "{}", value);
+        assert!((expected_utility(&tree, &profile)[0] - value).abs() < 1e-9);
+        assert!(exploitability(&tree, &profile) < 1e-9);
+    }
+
+    #[test]
+    fn cfr_matches_sequence_form() {
+        for config in [
+            KuhnConfig::default(),
+            KuhnConfig::new(2, 5, 1.0, 2.0).unwrap(),
+        ] {
+            let (tree, table) = train(&config, 10000);
+            let (value, _) = sequence_form::solve(&tree).unwrap();
+            let utility = expected_utility(&tree, &table.average_profile());
+
+            assert!(
+                (utility[0] - value).abs() < 2e-3,
+                "{} != {}",
+                utility[0],
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn reach_probabilities_split_the_deals() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let profile = vec![[1.0 / N_ACTIONS as f64; N_ACTIONS]; tree.info_sets.len()];
+        let reach = reach_probabilities(&tree, &profile);
+
+        for (info_set, pr) in tree.info_sets.iter().zip(reach.iter()) {
+            // One card in three, then half a chance per action on the way
+            let n_actions = info_set.key.len() - "J rr".len();
+            let expected = 1.0 / 3.0 / 2f64.powi(n_actions as i32);
+            assert!((pr - expected).abs() < 1e-12, "{} {}", info_set.key, pr);
+        }
+    }
+
+    #[test]
+    fn counterfactual_values_explain_the_regrets() {
+        let (tree, table) = train(&KuhnConfig::default(), 100);
+
+        // The regret of an action is its value minus the value of the strategy played,
+        // so the difference of two regrets is the difference of their values
+        for i in 0..tree.info_sets.len() {
+            let regret = &table.regret_sum[i * N_ACTIONS..(i + 1) * N_ACTIONS];
+            let value = &table.action_value_sum[i * N_ACTIONS..(i + 1) * N_ACTIONS];
+            assert!(((regret[1] - regret[0]) - (value[1] - value[0])).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn thread_count_does_not_change_the_tables() {
+        let tree = GameTree::new(&KuhnConfig::new(3, 8, 1.0, 2.0).unwrap());
+        let train = |n_threads| {
+            with_workers(&tree, n_threads, |workers| {
+                let mut table = InfoSetTable::new(tree.info_sets.len());
+                for _ in 0..50 {
+                    workers.chance_util(&mut table);
+                    table.next_strategy();
+                }
+                (workers.n_threads(), table)
+            })
+        };
+
+        // The blocks of deals are merged in order, so the sums are the same to the bit
+        let ((n_one, one), (n_four, four)) = (train(1), train(4));
+        assert_eq!((n_one, n_four), (1, 4));
+        assert_eq!(one.regret_sum, four.regret_sum);
+        assert_eq!(one.strategy_sum, four.strategy_sum);
+        assert_eq!(one.reach_pr_sum, four.reach_pr_sum);
+
+        // Small trees stay on the calling thread
+        let kuhn = GameTree::new(&KuhnConfig::default());
+        assert_eq!(with_workers(&kuhn, 4, |workers| workers.n_threads()), 1);
+    }
+
+    #[test]
+    fn ante_and_bet_set_the_payoffs() {
+        let config = KuhnConfig::new(2, 5, 2.0, 3.0).unwrap();
+        let cases: [(&str, [isize; 2], [f64; 2]); 6] = [
+            // A showdown after two checks is worth the ante
+            ("rrcc", [4, 1], [2.0, -2.0]),
+            ("rrcc", [0, 3], [-2.0, 2.0]),
+            // A fold loses the ante, whatever the cards
+            ("rrbc", [0, 4], [2.0, -2.0]),
+            ("rrcbc", [4, 0], [-2.0, 2.0]),
+            // A called bet is worth the ante and the bet
+            ("rrbb", [1, 4], [-5.0, 5.0]),
+            ("rrcbb", [3, 2], [5.0, -5.0]),
+        ];
+
+        for (history, cards, utility) in cases {
+            let actual = terminal_util(&config, history, &cards);
+            assert_eq!(actual, [utility[0], utility[1], 0.0, 0.0], "{}", history);
+        }
+
+        // One deal for every ordered pair of the five cards, and the top five ranks
+        let tree = GameTree::new(&config);
+        let Node::Chance { outcomes } = &tree.nodes[tree.root] else {
+            panic!("the game starts with the deal");
+        };
+        assert_eq!(outcomes.len(), 20);
+        assert_eq!(config.card_str(0), "9");
+        assert_eq!(config.card_str(4), "K");
+    }
+
+    #[test]
+    fn three_player_hands_end_after_every_player_answers() {
+        let config = KuhnConfig::new(3, 4, 1.0, 1.0).unwrap();
+
+        for history in ["rrccc", "rrbcc", "rrbbc", "rrcbcc", "rrcbbb", "rrccbbc"] {
+            assert!(is_terminal(&config, history), "{}", history);
+        }
+        for history in ["rrcc", "rrbc", "rrcbb", "rrccbb"] {
+            assert!(!is_terminal(&config, history), "{}", history);
+        }
+    }
+
+    #[test]
+    fn three_player_terminals_pay_the_pot() {
+        let config = KuhnConfig::new(3, 4, 1.0, 1.0).unwrap();
+        let cases: [(&str, [isize; 3], Utility); 6] = [
+            // Every player checks, the highest card wins the antes
+            ("rrccc", [2, 0, 1], [2.0, -1.0, -1.0, 0.0]),
+            // Everyone folds to the bet, even with better cards
+            ("rrbcc", [0, 3, 2], [2.0, -1.0, -1.0, 0.0]),
+            ("rrcbcc", [3, 0, 2], [-1.0, 2.0, -1.0, 0.0]),
+            // One caller, the player who folded only loses the ante
+            ("rrbbc", [1, 3, 2], [-2.0, 3.0, -1.0, 0.0]),
+            // Two callers, the highest card of the three takes the pot
+            ("rrcbbb", [0, 3, 1], [-2.0, 4.0, -2.0, 0.0]),
+            ("rrccbbb", [3, 1, 0], [4.0, -2.0, -2.0, 0.0]),
+        ];
+
+        for (history, cards, utility) in cases {
+            let actual = terminal_util(&config, history, &cards);
+            assert_eq!(actual, utility, "{} {:?}", history, cards);
+            assert_eq!(actual.iter().sum::<f64>(), 0.0);
+        }
+    }
+
+    #[test]
+    fn three_player_cfr_is_zero_sum() {
+        let (tree, table) = train(&KuhnConfig::new(3, 3, 1.0, 1.0).unwrap(), 200);
+
+        for node in &tree.nodes {
+            if let Node::Terminal { utility } = node {
+                assert!(utility.iter().sum::<f64>().abs() < 1e-12, "{:?}", utility);
+            }
+        }
+
+        let utility = expected_utility(&tree, &table.average_profile());
+        assert!(utility.iter().sum::<f64>().abs() < 1e-9, "{:?}", utility);
+        assert_eq!(utility[3], 0.0);
+    }
+
+    #[test]
+    fn seeded_simulations_replay() {
+        let (tree, table) = train(&KuhnConfig::default(), 1000);
+        let profile = table.average_profile();
+        let play = |seed| simulate(&tree, &profile, 1000, &mut StdRng::seed_from_u64(seed));
+
+        assert_eq!(play(1), play(1));
+        assert_ne!(play(1), play(2));
+
+        // Kuhn hands are worth at most 2, 100000 of them land close to the game value
+        let mean = simulate(&tree, &profile, 100000, &mut StdRng::seed_from_u64(0));
+        assert!((mean[0] + 1.0 / 18.0).abs() < 0.02, "{:?}", mean);
+    }
+
+    #[test]
+    fn duplicate_matches_estimate_the_expected_winnings() {
+        let (tree, table) = train(&KuhnConfig::default(), 1000);
+        let equilibrium = table.average_profile();
+        let always_bet = vec![[0.0, 1.0]; tree.info_sets.len()];
+
+        // Half of the hands in each seat
+        let expected = (0..tree.n_players)
+            .map(|seat| {
+                let profile = seat_profile(&tree, &equilibrium, &always_bet, seat);
+                expected_utility(&tree, &profile)[seat]
+            })
+            .sum::<f64>()
+            / tree.n_players as f64;
+
+        let play = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            duplicate_match(&tree, &equilibrium, &always_bet, 20000, &mut rng)
+        };
+        assert_eq!(play(1), play(1));
+
+        let estimate = Estimate::new(&play(0));
+        assert!(expected > 0.0);
+        assert!(
+            (estimate.mean - expected).abs() < 4.0 * estimate.std_error,
+            "{:?} {}",
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn equilibrium_beats_every_baseline() {
+        let (tree, table) = train(&KuhnConfig::default(), 10000);
+        let equilibrium = table.average_profile();
+
+        for baseline in BASELINES {
+            let profile = baseline.profile(&tree);
+            let winnings = (0..tree.n_players)
+                .map(|seat| {
+                    let profile = seat_profile(&tree, &equilibrium, &profile, seat);
+                    expected_utility(&tree, &profile)[seat]
+                })
+                .sum::<f64>()
+                / tree.n_players as f64;
+
+            assert!(winnings > 0.01, "{}: {}", baseline, winnings);
+            assert_eq!(baseline.to_string().parse(), Ok(baseline));
+        }
+    }
+
+    #[test]
+    fn baselines_answer_bets_made_before_them() {
+        let tree = GameTree::new(&KuhnConfig::new(3, 4, 1.0, 1.0).unwrap());
+        let strategy = |baseline: Baseline, history: &str| {
+            let i = tree
+                .info_sets
+                .iter()
+                .position(|info_set| info_set.history == history && info_set.card == 0)
+                .unwrap();
+            baseline.strategy(&tree, i)
+        };
+
+        // The lowest card checks when it can and calls once someone has bet
+        assert_eq!(strategy(Baseline::AlwaysCall, "rrcc"), [1.0, 0.0]);
+        assert_eq!(strategy(Baseline::AlwaysCall, "rrbc"), [0.0, 1.0]);
+        assert_eq!(strategy(Baseline::AlwaysCall, "rrcb"), [0.0, 1.0]);
+        assert_eq!(strategy(Baseline::HandStrength(0.5), "rrcb"), [1.0, 0.0]);
+    }
+
+    #[test]
+    fn tournament_ranks_the_trained_strategy_first() {
+        let (tree, table) = train(&KuhnConfig::default(), 1000);
+        let always_bet = vec![[0.0, 1.0]; tree.info_sets.len()];
+        let uniform = vec![[0.5, 0.5]; tree.info_sets.len()];
+        let entries = vec![
+            ("always_bet".to_string(), always_bet),
+            ("trained".to_string(), table.average_profile()),
+            ("uniform".to_string(), uniform),
+        ];
+        let tournament = round_robin(&tree, &entries, 2000, 0);
+
+        assert_eq!(tournament.standings()[0], 1);
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(tournament.winnings[i][j], -tournament.winnings[j][i]);
+                assert!((tournament.scores[i][j] + tournament.scores[j][i] - 1.0).abs() < 1e-12);
+                assert!(
+                    (tournament.win_rates[i][j] + tournament.win_rates[j][i] - 1.0).abs() < 1e-12
+                );
+            }
+        }
+
+        // The matches are scored on the winnings, so no strategy is ranked above one
+        // it loses money to
+        let standings = tournament.standings();
+        for (rank, &i) in standings.iter().enumerate() {
+            for &j in &standings[rank + 1..] {
+                assert!(tournament.winnings[i][j] > 0.0, "{:?}", tournament.winnings);
+                assert_eq!(tournament.scores[i][j], 1.0);
+            }
+        }
+
+        // A header and a row per strategy, plus the separator in Markdown
+        assert_eq!(tournament.ratings_csv().lines().count(), 4);
+        assert_eq!(tournament.winnings_markdown().lines().count(), 5);
+        assert_eq!(tournament.win_rates_markdown().lines().count(), 5);
+        assert!(tournament.winnings_csv().starts_with("strategy,trained,"));
+        assert!(tournament
+            .ratings_csv()
+            .starts_with("rank,strategy,rating,score,"));
+    }
+
+    #[test]
+    fn saved_strategies_load_back() {
+        let (tree, table) = train(&KuhnConfig::default(), 100);
+        let profile = table.average_profile();
+        let path = std::env::temp_dir().join(format!("kuhn-{}.txt", process::id()));
+        let path = path.to_str().unwrap();
+
+        strategy_file::save(&tree, &profile, path).unwrap();
+        let loaded = strategy_file::load(&tree, path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        for (a, b) in profile.iter().zip(loaded.iter()) {
+            assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn uniform_profile_is_exploitable() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let profile = vec![[1.0 / N_ACTIONS as f64; N_ACTIONS]; tree.info_sets.len()];
+
+        assert!(exploitability(&tree, &profile) > 0.1);
     }
 }
@@ -0,0 +1,182 @@
+use std::fmt;
+
+use crate::best_response::{best_response, expected_utility};
+use crate::locking::Lock;
+use crate::tree::GameTree;
+use crate::{CHANCE_ACTIONS, N_ACTIONS};
+
+// Where the opponent model comes from, `--opponent FILE` or `--opponent-log FILE`
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpponentSource {
+    // One `KEY=P1,P2` line per information set, like `--lock`
+    Table(String),
+    // One observed decision per line, `KEY ACTION` e.g. `J rrb c`
+    Log(String),
+}
+
+impl fmt::Display for OpponentSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OpponentSource::Table(path) => write!(f, "table {}", path),
+            OpponentSource::Log(path) => write!(f, "log {}", path),
+        }
+    }
+}
+
+// Strategies of the modelled players at the information sets the model knows, the
+// other information sets keep the equilibrium strategy
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpponentModel {
+    pub strategies: Vec<(usize, [f64; N_ACTIONS])>,
+}
+
+impl OpponentModel {
+    pub fn load(tree: &GameTree, source: &OpponentSource) -> Result<OpponentModel, String> {
+        let (OpponentSource::Table(path) | OpponentSource::Log(path)) = source;
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("cannot read {}: {}", path, error))?;
+
+        match source {
+            OpponentSource::Table(_) => OpponentModel::from_table(tree, &text),
+            OpponentSource::Log(_) => OpponentModel::from_log(tree, &text),
+        }
+    }
+
+    // Blank lines and lines starting with '#' are skipped
+    pub fn from_table(tree: &GameTree, text: &str) -> Result<OpponentModel, String> {
+        let strategies = lines(text)
+            .map(|line| {
+                let lock: Lock = line.parse()?;
+                Ok((lock.info_set(tree)?, lock.strategy))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(OpponentModel { strategies })
+    }
+
+    // The model plays every action as often as it was observed
+    pub fn from_log(tree: &GameTree, text: &str) -> Result<OpponentModel, String> {
+        let mut counts = vec![[0.0; N_ACTIONS]; tree.info_sets.len()];
+
+        for line in lines(text) {
+            let (key, action) = line
+                .rsplit_once(' ')
+                .ok_or_else(|| format!("expected KEY ACTION in log line '{}'", line))?;
+
+            let info_set = tree
+                .info_sets
+                .iter()
+                .position(|info_set| info_set.key == key.trim())
+                .ok_or_else(|| format!("unknown information set in log line '{}'", line))?;
+            let action = CHANCE_ACTIONS
+                .iter()
+                .position(|&name| name == action)
+                .ok_or_else(|| format!("unknown action in log line '{}'", line))?;
+
+            counts[info_set][action] += 1.0;
+        }
+
+        let strategies = counts
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, count)| {
+                let total = count.iter().sum::<f64>();
+                (total > 0.0).then(|| (i, count.map(|x| x / total)))
+            })
+            .collect();
+
+        Ok(OpponentModel { strategies })
+    }
+
+    // `profile` with the modelled information sets replaced
+    pub fn apply(&self, profile: &[[f64; N_ACTIONS]]) -> Vec<[f64; N_ACTIONS]> {
+        let mut profile = profile.to_vec();
+        for &(info_set, strategy) in &self.strategies {
+            profile[info_set] = strategy;
+        }
+        profile
+    }
+
+    // The players without a modelled information set, or every player when the model
+    // covers all of them
+    pub fn exploiting_players(&self, tree: &GameTree) -> Vec<usize> {
+        let modelled: Vec<usize> = self
+            .strategies
+            .iter()
+            .map(|&(info_set, _)| tree.info_sets[info_set].player)
+            .collect();
+        let players: Vec<usize> = (0..tree.n_players)
+            .filter(|player| !modelled.contains(player))
+            .collect();
+
+        if players.is_empty() {
+            (0..tree.n_players).collect()
+        } else {
+            players
+        }
+    }
+}
+
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+// What one player wins against the model with a best response and with the
+// equilibrium strategy, the best response picks one action per information set
+#[derive(Debug, Clone)]
+pub struct Exploitation {
+    pub best_response: f64,
+    pub equilibrium: f64,
+    pub actions: Vec<Option<usize>>,
+}
+
+pub fn exploit(
+    tree: &GameTree,
+    model: &OpponentModel,
+    equilibrium: &[[f64; N_ACTIONS]],
+    player: usize,
+) -> Exploitation {
+    let opponents = model.apply(equilibrium);
+    let response = best_response(tree, &opponents, player);
+
+    // The player keeps the equilibrium while the others follow the model
+    let mut profile = opponents;
+    for (i, info_set) in tree.info_sets.iter().enumerate() {
+        if info_set.player == player {
+            profile[i] = equilibrium[i];
+        }
+    }
+
+    Exploitation {
+        best_response: response.value,
+        equilibrium: expected_utility(tree, &profile)[player],
+        actions: response.actions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KuhnConfig;
+
+    #[test]
+    fn logs_give_the_observed_frequencies() {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let log = "# player 2 facing a bet\nJ rrb c\nJ rrb c\nJ rrb b\n\nK rrc b\n";
+        let model = OpponentModel::from_log(&tree, log).unwrap();
+
+        let find = |key: &str| {
+            let i = tree.info_sets.iter().position(|x| x.key == key).unwrap();
+            model.strategies.iter().find(|&&(j, _)| j == i).unwrap().1
+        };
+        assert_eq!(model.strategies.len(), 2);
+        assert!((find("J rrb")[0] - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(find("K rrc"), [0.0, 1.0]);
+        assert_eq!(model.exploiting_players(&tree), vec![0]);
+
+        assert!(OpponentModel::from_log(&tree, "J rrb x").is_err());
+        assert!(OpponentModel::from_log(&tree, "J zz c").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{self, BufRead, Write};
+
+use common::simulate::sample;
+use rand::Rng;
+
+use crate::config::card_name;
+use crate::tree::{GameTree, Node};
+use crate::{is_facing_bet, CHANCE_ACTIONS, N_ACTIONS};
+
+// A human against the average strategies in the terminal, e.g. `cargo run -p task2 -- --play`
+//
+// The human takes the seats in turn while the other seats sample their actions from
+// `profile`. Hands are dealt until the human answers `q` or the input ends, the
+// total winnings of the human are returned
+pub fn play<R: Rng, I: BufRead, O: Write>(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    rng: &mut R,
+    input: &mut I,
+    output: &mut O,
+) -> io::Result<f64> {
+    let mut score = 0.0;
+
+    for hand in 0.. {
+        let seat = hand % tree.n_players;
+        writeln!(output, "\nhand {}, you are player {}", hand + 1, seat + 1)?;
+
+        let Some(winnings) = play_hand(tree, profile, seat, rng, input, output)? else {
+            break;
+        };
+
+        score += winnings;
+        writeln!(output, "score after {} hands: {:+}", hand + 1, score)?;
+    }
+
+    Ok(score)
+}
+
+// Winnings of the human in one hand, `None` when they quit
+fn play_hand<R: Rng, I: BufRead, O: Write>(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    seat: usize,
+    rng: &mut R,
+    input: &mut I,
+    output: &mut O,
+) -> io::Result<Option<f64>> {
+    let mut node = tree.root;
+    let mut actions = String::new();
+    // The card of every player, read from their last information set
+    let mut cards = vec![""; tree.n_players];
+
+    loop {
+        node = match &tree.nodes[node] {
+            Node::Terminal { utility } => {
+                show_down(tree.n_players, seat, &actions, &cards, output)?;
+
+                let winnings = utility[seat];
+                if winnings > 0.0 {
+                    writeln!(output, "you win {}", winnings)?;
+                } else if winnings == 0.0 {
+                    writeln!(output, "you break even")?;
+                } else {
+                    writeln!(output, "you lose {}", -winnings)?;
+                }
+                return Ok(Some(winnings));
+            }
+            Node::Chance { outcomes } => {
+                let weights: Vec<f64> = outcomes.iter().map(|&(pr, _)| pr).collect();
+                outcomes[sample(&weights, rng)].1
+            }
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                cards[*player] = card_name(tree.n_ranks, tree.info_sets[*info_set].card);
+                let facing_bet = is_facing_bet(&tree.info_sets[*info_set].history);
+
+                let action = if *player == seat {
+                    match ask(cards[seat], &actions, facing_bet, input, output)? {
+                        Some(action) => action,
+                        None => return Ok(None),
+                    }
+                } else {
+                    let action = sample(&profile[*info_set], rng);
+                    writeln!(
+                        output,
+                        "player {} {}",
+                        player + 1,
+                        describe(action, facing_bet)
+                    )?;
+                    action
+                };
+
+                actions.push_str(CHANCE_ACTIONS[action]);
+                children[action]
+            }
+        };
+    }
+}
+
+// Prompt until the human picks an action, `None` when they quit
+fn ask<I: BufRead, O: Write>(
+    card: &str,
+    actions: &str,
+    facing_bet: bool,
+    input: &mut I,
+    output: &mut O,
+) -> io::Result<Option<usize>> {
+    let choices = (0..N_ACTIONS)
+        .map(|action| format!("{} {}", CHANCE_ACTIONS[action], verb(action, facing_bet)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    loop {
+        write!(
+            output,
+            "your card {}, actions so far '{}', {} or q to quit: ",
+            card, actions, choices
+        )?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim() == "q" {
+            return Ok(None);
+        }
+
+        if let Some(action) = CHANCE_ACTIONS.iter().position(|&name| name == line.trim()) {
+            return Ok(Some(action));
+        }
+        writeln!(output, "choose one of {}", choices)?;
+    }
+}
+
+// After a bet checking folds and betting calls
+fn verb(action: usize, facing_bet: bool) -> &'static str {
+    match (action, facing_bet) {
+        (0, false) => "check",
+        (0, true) => "fold",
+        (_, false) => "bet",
+        (_, true) => "call",
+    }
+}
+
+fn describe(action: usize, facing_bet: bool) -> &'static str {
+    match (action, facing_bet) {
+        (0, false) => "checks",
+        (0, true) => "folds",
+        (_, false) => "bets",
+        (_, true) => "calls",
+    }
+}
+
+// Show the cards of the bots still in the hand when more than one player is left
+fn show_down<O: Write>(
+    n_players: usize,
+    seat: usize,
+    actions: &str,
+    cards: &[&str],
+    output: &mut O,
+) -> io::Result<()> {
+    let mut folded = vec![false; n_players];
+    if let Some(bet) = actions.find('b') {
+        for (i, op) in actions.chars().enumerate().skip(bet) {
+            folded[i % n_players] |= op == 'c';
+        }
+    }
+
+    if folded.iter().filter(|&&x| !x).count() < 2 {
+        return Ok(());
+    }
+
+    for player in (0..n_players).filter(|&player| player != seat && !folded[player]) {
+        writeln!(output, "player {} shows {}", player + 1, cards[player])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KuhnConfig;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn session(input: &str, seed: u64) -> (f64, String) {
+        let tree = GameTree::new(&KuhnConfig::default());
+        let profile = vec![[0.5; N_ACTIONS]; tree.info_sets.len()];
+        let mut output = Vec::new();
+
+        let score = play(
+            &tree,
+            &profile,
+            &mut StdRng::seed_from_u64(seed),
+            &mut input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        (score, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn hands_are_played_until_the_human_quits() {
+        let (score, output) = session("x\nb\nb\nb\nb\nq\n", 3);
+
+        assert!(output.contains("choose one of c check, b bet"));
+        assert!(output.contains("hand 2, you are player 2"));
+        assert!(output.contains("score after 1 hands"));
+        // Betting and calling every time always goes to a showdown
+        assert!(output.contains("player 2 shows") || output.contains("player 1 shows"));
+        assert_eq!(session("x\nb\nb\nb\nb\nq\n", 3), (score, output));
+    }
+}
@@ -0,0 +1,120 @@
+use crate::best_response::{best_response, expected_utility};
+use crate::info_set::{InfoSetTable, InformationSet};
+use crate::opponent::OpponentModel;
+use crate::tree::{GameTree, Node};
+use crate::N_ACTIONS;
+
+// Restricted Nash response, e.g. `--opponent model.txt --rnr 0,0.5,1`
+//
+// Chance first decides whether the opponent follows the model, with probability `p`,
+// or plays freely. The exploiter cannot tell the two apart, so CFR on this game
+// trades what the exploiter wins against the model for how much a best response
+// wins against the exploiter. The modelled information sets get a copy locked to the
+// model, the ones the model leaves out are shared with the free opponent.
+
+// The game with the fixed opponent, the copies of the modelled information sets are
+// appended to `info_sets` in the order of `OpponentModel::strategies`
+pub fn restricted_tree(tree: &GameTree, model: &OpponentModel, p: f64) -> GameTree {
+    let mut info_sets: Vec<InformationSet> = tree
+        .info_sets
+        .iter()
+        .map(|info_set| {
+            InformationSet::new(
+                &info_set.key,
+                info_set.player,
+                info_set.card,
+                &info_set.history,
+            )
+        })
+        .collect();
+
+    let free: Vec<usize> = (0..tree.info_sets.len()).collect();
+    let mut fixed = free.clone();
+    for &(info_set, _) in &model.strategies {
+        let original = &tree.info_sets[info_set];
+        fixed[info_set] = info_sets.len();
+        info_sets.push(InformationSet::new(
+            &original.key,
+            original.player,
+            original.card,
+            &original.history,
+        ));
+    }
+
+    let Node::Chance { outcomes: deals } = &tree.nodes[tree.root] else {
+        unreachable!("the game starts with the deal");
+    };
+
+    // Both copies keep the layout of the original tree, shifted by `offset`
+    let mut nodes = Vec::new();
+    let mut outcomes = Vec::new();
+    for (pr, info_set_map) in [(p, &fixed), (1.0 - p, &free)] {
+        if pr == 0.0 {
+            continue;
+        }
+
+        let offset = nodes.len();
+        nodes.extend(tree.nodes.iter().map(|node| {
+            match node {
+                Node::Chance { outcomes } => Node::Chance {
+                    outcomes: outcomes
+                        .iter()
+                        .map(|&(pr, child)| (pr, child + offset))
+                        .collect(),
+                },
+                Node::Terminal { utility } => Node::Terminal { utility: *utility },
+                Node::Decision {
+                    player,
+                    info_set,
+                    children,
+                } => Node::Decision {
+                    player: *player,
+                    info_set: info_set_map[*info_set],
+                    children: children.map(|child| child + offset),
+                },
+            }
+        }));
+        outcomes.extend(
+            deals
+                .iter()
+                .map(|&(deal_pr, child)| (pr * deal_pr, child + offset)),
+        );
+    }
+
+    nodes.push(Node::Chance { outcomes });
+    GameTree {
+        root: nodes.len() - 1,
+        nodes,
+        info_sets,
+        n_players: tree.n_players,
+        n_ranks: tree.n_ranks,
+    }
+}
+
+// Lock the copies of the modelled information sets in a table of the restricted game
+pub fn lock_model(table: &mut InfoSetTable, tree: &GameTree, model: &OpponentModel) {
+    for (i, (_, strategy)) in model.strategies.iter().enumerate() {
+        table.lock(tree.info_sets.len() + i, strategy);
+    }
+}
+
+// One point of the curve, what the exploiter wins with its strategy in `profile`
+// against the model and against a best response of the opponent
+#[derive(Debug, Clone, Copy)]
+pub struct CurvePoint {
+    pub against_model: f64,
+    pub worst_case: f64,
+}
+
+pub fn evaluate(
+    tree: &GameTree,
+    model: &OpponentModel,
+    profile: &[[f64; N_ACTIONS]],
+    exploiter: usize,
+) -> CurvePoint {
+    // The game is zero-sum, the best response wins what the exploiter loses
+    CurvePoint {
+        against_model: expected_utility(tree, &model.apply(profile))[exploiter],
+        worst_case: -best_response(tree, profile, 1 - exploiter).value,
+    }
+}
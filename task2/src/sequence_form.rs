@@ -0,0 +1,168 @@
+use common::simplex::{LinearProgram, Relation};
+
+use crate::tree::{GameTree, Node};
+use crate::N_ACTIONS;
+
+// Exact equilibrium of a two-player game through the sequence-form linear program,
+// returns the value for player 1 and the strategy of every information set
+pub fn solve(tree: &GameTree) -> Result<(f64, Vec<[f64; N_ACTIONS]>), String> {
+    if tree.n_players != 2 {
+        return Err(format!(
+            "the sequence form needs two players, got {}",
+            tree.n_players
+        ));
+    }
+
+    let form = SequenceForm::new(tree);
+    let mut profile = vec![[1.0 / N_ACTIONS as f64; N_ACTIONS]; tree.info_sets.len()];
+
+    let (value, x) = form.best_plan(0)?;
+    form.fill_strategies(0, &x, &mut profile);
+
+    let (_, y) = form.best_plan(1)?;
+    form.fill_strategies(1, &y, &mut profile);
+
+    Ok((value, profile))
+}
+
+// A sequence is the list of a player's own actions up to some point, every player
+// has the empty sequence 0 and one sequence per action of their information sets
+struct SequenceForm {
+    n_sequences: [usize; 2],
+    // Information sets of each player in the order they were found
+    info_sets: [Vec<usize>; 2],
+    // Sequence leading to an information set, indexed by information set
+    parent: Vec<usize>,
+    // Sequence of each action of an information set, `info_set * N_ACTIONS + action`
+    sequence: Vec<usize>,
+    // Chance-weighted utility of player 1 for each pair of sequences ending the game
+    payoffs: Vec<((usize, usize), f64)>,
+}
+
+impl SequenceForm {
+    fn new(tree: &GameTree) -> SequenceForm {
+        let mut form = SequenceForm {
+            n_sequences: [1, 1],
+            info_sets: [Vec::new(), Vec::new()],
+            parent: vec![usize::MAX; tree.info_sets.len()],
+            sequence: vec![usize::MAX; tree.info_sets.len() * N_ACTIONS],
+            payoffs: Vec::new(),
+        };
+
+        form.collect(tree, tree.root, [0, 0], 1.0);
+        form
+    }
+
+    fn collect(&mut self, tree: &GameTree, node: usize, sequences: [usize; 2], pr: f64) {
+        match &tree.nodes[node] {
+            Node::Terminal { utility } => {
+                self.payoffs
+                    .push(((sequences[0], sequences[1]), pr * utility[0]));
+            }
+            Node::Chance { outcomes } => {
+                for &(child_pr, child) in outcomes {
+                    self.collect(tree, child, sequences, pr * child_pr);
+                }
+            }
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                if self.parent[*info_set] == usize::MAX {
+                    self.parent[*info_set] = sequences[*player];
+                    self.info_sets[*player].push(*info_set);
+
+                    for action in 0..N_ACTIONS {
+                        self.sequence[*info_set * N_ACTIONS + action] = self.n_sequences[*player];
+                        self.n_sequences[*player] += 1;
+                    }
+                }
+
+                for (action, &child) in children.iter().enumerate() {
+                    let mut child_sequences = sequences;
+                    child_sequences[*player] = self.sequence[*info_set * N_ACTIONS + action];
+                    self.collect(tree, child, child_sequences, pr);
+                }
+            }
+        }
+    }
+
+    // The realization plan of `player` maximising their worst case utility
+    //
+    // The variables are the plan x of the player followed by the free values q of the
+    // opponent's information sets, split into positive and negative parts. The
+    // opponent picks the sequences minimising the utility, which gives one constraint
+    // per opponent sequence, and q of the root is the value of the game
+    fn best_plan(&self, player: usize) -> Result<(f64, Vec<f64>), String> {
+        let opponent = 1 - player;
+        let n_plan = self.n_sequences[player];
+        let n_values = self.info_sets[opponent].len() + 1;
+        let n_variables = n_plan + 2 * n_values;
+        let sign = if player == 0 { 1.0 } else { -1.0 };
+
+        let mut objective = vec![0.0; n_variables];
+        objective[n_plan] = 1.0;
+        objective[n_plan + n_values] = -1.0;
+        let mut program = LinearProgram::new(objective);
+
+        // Value column of the opponent's root and information sets
+        let mut constraints = vec![vec![0.0; n_variables]; self.n_sequences[opponent]];
+        let mut add_value = |row: usize, value: usize, coefficient: f64| {
+            constraints[row][n_plan + value] += coefficient;
+            constraints[row][n_plan + n_values + value] -= coefficient;
+        };
+        add_value(0, 0, 1.0);
+        for (i, &info_set) in self.info_sets[opponent].iter().enumerate() {
+            add_value(self.parent[info_set], i + 1, -1.0);
+            for action in 0..N_ACTIONS {
+                add_value(self.sequence[info_set * N_ACTIONS + action], i + 1, 1.0);
+            }
+        }
+
+        for &(sequences, utility) in &self.payoffs {
+            let (own, other) = if player == 0 {
+                sequences
+            } else {
+                (sequences.1, sequences.0)
+            };
+            constraints[other][own] -= sign * utility;
+        }
+
+        for coefficients in constraints {
+            program.add_constraint(coefficients, Relation::LessEqual, 0.0);
+        }
+
+        // The plan starts with the empty sequence and splits the probability of each
+        // information set over its actions
+        let mut root = vec![0.0; n_variables];
+        root[0] = 1.0;
+        program.add_constraint(root, Relation::Equal, 1.0);
+
+        for &info_set in &self.info_sets[player] {
+            let mut coefficients = vec![0.0; n_variables];
+            coefficients[self.parent[info_set]] -= 1.0;
+            for action in 0..N_ACTIONS {
+                coefficients[self.sequence[info_set * N_ACTIONS + action]] += 1.0;
+            }
+            program.add_constraint(coefficients, Relation::Equal, 0.0);
+        }
+
+        let (value, variables) = program.maximize()?;
+        Ok((value * sign, variables[..n_plan].to_vec()))
+    }
+
+    // Turn a realization plan back into behaviour strategies, information sets the
+    // plan never reaches keep the uniform strategy
+    fn fill_strategies(&self, player: usize, plan: &[f64], profile: &mut [[f64; N_ACTIONS]]) {
+        for &info_set in &self.info_sets[player] {
+            let reach_pr = plan[self.parent[info_set]];
+
+            if reach_pr > 1e-9 {
+                for (action, x) in profile[info_set].iter_mut().enumerate() {
+                    *x = plan[self.sequence[info_set * N_ACTIONS + action]].max(0.0) / reach_pr;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use std::fs;
+
+use crate::opponent::{OpponentModel, OpponentSource};
+use crate::tree::GameTree;
+use crate::N_ACTIONS;
+
+// Strategies written by `--save FILE` and played by `--match`, one `KEY=P1,P2` line
+// per information set like `--lock` and `--opponent`
+
+pub fn save(tree: &GameTree, profile: &[[f64; N_ACTIONS]], path: &str) -> Result<(), String> {
+    let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
+    items.sort_by(|a, b| a.1.key.cmp(&b.1.key));
+
+    let text: String = items
+        .iter()
+        .map(|(i, info_set)| {
+            let probabilities: Vec<String> = profile[*i].iter().map(|x| x.to_string()).collect();
+            format!("{}={}\n", info_set.key, probabilities.join(","))
+        })
+        .collect();
+
+    fs::write(path, text).map_err(|error| format!("cannot write {}: {}", path, error))
+}
+
+// The information sets missing from the file are played uniformly
+pub fn load(tree: &GameTree, path: &str) -> Result<Vec<[f64; N_ACTIONS]>, String> {
+    let model = OpponentModel::load(tree, &OpponentSource::Table(path.to_string()))?;
+    let uniform = vec![[1.0 / N_ACTIONS as f64; N_ACTIONS]; tree.info_sets.len()];
+
+    Ok(model.apply(&uniform))
+}
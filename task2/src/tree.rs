@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use common::agent::InfoSets;
+use common::simulate::{Game, Move};
+
+use crate::config::KuhnConfig;
+use crate::info_set::InformationSet;
+use crate::{get_info_set_key, is_terminal, terminal_util, Utility, CHANCE_ACTIONS, N_ACTIONS};
+
+// A node of the game tree, children are indices into `GameTree::nodes`
+#[derive(Debug)]
+pub enum Node {
+    // Random moves with their probabilities
+    Chance {
+        outcomes: Vec<(f64, usize)>,
+    },
+    // End of the game, the utility of every player
+    Terminal {
+        utility: Utility,
+    },
+    // A player picks one of `CHANCE_ACTIONS`
+    Decision {
+        player: usize,
+        info_set: usize,
+        children: [usize; N_ACTIONS],
+    },
+}
+
+// The whole game, built once so that the CFR iterations only follow indices
+#[derive(Debug)]
+pub struct GameTree {
+    pub nodes: Vec<Node>,
+    pub info_sets: Vec<InformationSet>,
+    pub root: usize,
+    pub n_players: usize,
+    pub n_ranks: usize,
+}
+
+impl GameTree {
+    pub fn new(config: &KuhnConfig) -> GameTree {
+        let mut tree = GameTree {
+            nodes: Vec::new(),
+            info_sets: Vec::new(),
+            root: 0,
+            n_players: config.n_players,
+            n_ranks: config.n_ranks,
+        };
+        let mut indices = HashMap::new();
+
+        let deals = config.deals();
+        let n_possibilities = deals.len();
+
+        let outcomes = deals
+            .into_iter()
+            .map(|cards| {
+                let child = tree.build(config, &mut indices, "rr", &cards);
+                (1.0 / n_possibilities as f64, child)
+            })
+            .collect();
+
+        tree.root = tree.push(Node::Chance { outcomes });
+        tree
+    }
+
+    fn build(
+        &mut self,
+        config: &KuhnConfig,
+        indices: &mut HashMap<String, usize>,
+        history: &str,
+        cards: &[isize],
+    ) -> usize {
+        if is_terminal(config, history) {
+            return self.push(Node::Terminal {
+                utility: terminal_util(config, history, cards),
+            });
+        }
+
+        // The players act in turn, starting with player 1
+        let player = (history.len() - 2) % config.n_players;
+        let key = get_info_set_key(config, cards[player], history);
+
+        let info_sets = &mut self.info_sets;
+        let info_set = *indices.entry(key).or_insert_with_key(|key| {
+            info_sets.push(InformationSet::new(key, player, cards[player], history));
+            info_sets.len() - 1
+        });
+
+        let children = CHANCE_ACTIONS
+            .map(|action| self.build(config, indices, &format!("{}{}", history, action), cards));
+
+        self.push(Node::Decision {
+            player,
+            info_set,
+            children,
+        })
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+}
+
+impl InfoSets for GameTree {
+    fn n_info_sets(&self) -> usize {
+        self.info_sets.len()
+    }
+}
+
+impl Game<N_ACTIONS> for GameTree {
+    fn root(&self) -> usize {
+        self.root
+    }
+
+    fn n_players(&self) -> usize {
+        self.n_players
+    }
+
+    fn player(&self, info_set: usize) -> usize {
+        self.info_sets[info_set].player
+    }
+
+    fn node(&self, node: usize) -> Move<'_, N_ACTIONS> {
+        match &self.nodes[node] {
+            Node::Chance { outcomes } => Move::Chance(outcomes),
+            Node::Terminal { .. } => Move::Terminal,
+            Node::Decision {
+                info_set, children, ..
+            } => Move::Decision {
+                info_set: *info_set,
+                children: children.map(Some),
+            },
+        }
+    }
+
+    fn winnings(&self, node: usize, player: usize) -> f64 {
+        let Node::Terminal { utility } = &self.nodes[node] else {
+            unreachable!("only terminal nodes pay out");
+        };
+        utility[player]
+    }
+}
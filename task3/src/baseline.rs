@@ -0,0 +1,124 @@
+use std::fmt;
+use std::str::FromStr;
+
+use common::agent::Agent;
+use common::minimizer::uniform_strategy;
+
+use crate::betting::BettingState;
+use crate::tree::GameTree;
+use crate::{N_ACTIONS, N_RANKS};
+
+// Rule-based bots to benchmark the trained strategies against, e.g. `--baselines`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Baseline {
+    // Checks and calls every raise
+    AlwaysCall,
+    // Raises whenever the cap allows it and calls otherwise
+    AlwaysRaise,
+    // Picks one of the legal actions with the same probability
+    Random,
+    // Raises with a King, otherwise plays like `AlwaysCall`
+    RaiseWithKing,
+    // Raises when the strength of its hand reaches the threshold, otherwise checks and
+    // folds, see `hand_strength`
+    HandStrength(f64),
+}
+
+pub const BASELINES: [Baseline; 5] = [
+    Baseline::AlwaysCall,
+    Baseline::AlwaysRaise,
+    Baseline::Random,
+    Baseline::RaiseWithKing,
+    Baseline::HandStrength(0.5),
+];
+
+const CALL: usize = 0;
+const RAISE: usize = 1;
+const FOLD: usize = 2;
+
+impl Agent<GameTree, N_ACTIONS> for Baseline {
+    fn strategy(&self, tree: &GameTree, info_set: usize) -> [f64; N_ACTIONS] {
+        let info_set = &tree.info_sets[info_set];
+        let legal = info_set.legal;
+        let raise = if legal[RAISE] { RAISE } else { CALL };
+        let facing_raise = BettingState::new(&info_set.key.history()).facing_raise();
+        let give_up = if facing_raise { FOLD } else { CALL };
+
+        let action = match *self {
+            Baseline::AlwaysCall => CALL,
+            Baseline::AlwaysRaise => raise,
+            Baseline::Random => return uniform_strategy(&legal),
+            Baseline::RaiseWithKing => {
+                if info_set.key.card == N_RANKS - 1 {
+                    raise
+                } else {
+                    CALL
+                }
+            }
+            Baseline::HandStrength(threshold) => {
+                if hand_strength(info_set.key.card, &info_set.key.public_cards) >= threshold {
+                    raise
+                } else {
+                    give_up
+                }
+            }
+        };
+
+        let mut strategy = [0.0; N_ACTIONS];
+        strategy[action] = 1.0;
+        strategy
+    }
+}
+
+// Rank of the private card scaled to [0, 1] before the public card, a pair is worth 1
+// and the other hands stay below it once the public card is shown
+fn hand_strength(card: usize, public_cards: &[usize]) -> f64 {
+    if public_cards.is_empty() {
+        card as f64 / (N_RANKS - 1) as f64
+    } else if public_cards.contains(&card) {
+        1.0
+    } else {
+        card as f64 / N_RANKS as f64
+    }
+}
+
+impl FromStr for Baseline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Baseline, String> {
+        let threshold = |threshold: &str| {
+            threshold
+                .parse::<f64>()
+                .ok()
+                .filter(|threshold| (0.0..=1.0).contains(threshold))
+                .ok_or_else(|| format!("expected a threshold between 0 and 1 in baseline {}", s))
+        };
+
+        match s {
+            "call" => Ok(Baseline::AlwaysCall),
+            "raise" => Ok(Baseline::AlwaysRaise),
+            "random" => Ok(Baseline::Random),
+            "king" => Ok(Baseline::RaiseWithKing),
+            "strength" => Ok(Baseline::HandStrength(0.5)),
+            _ => match s.split_once(':') {
+                Some(("strength", value)) => Ok(Baseline::HandStrength(threshold(value)?)),
+                _ => Err(format!(
+                    "unknown baseline {}, expected call, raise, random, king or strength:THRESHOLD",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Baseline::AlwaysCall => write!(f, "call"),
+            Baseline::AlwaysRaise => write!(f, "raise"),
+            Baseline::Random => write!(f, "random"),
+            Baseline::RaiseWithKing => write!(f, "king"),
+            Baseline::HandStrength(threshold) => write!(f, "strength:{}", threshold),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::tree::{GameTree, Node};
+use crate::N_ACTIONS;
+
+// Utility of player 1 when both players follow `profile`, where `profile[i]` is the
+// strategy played at information set `i`
+pub fn expected_utility(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> f64 {
+    node_utility(tree, profile, tree.root)
+}
+
+fn node_utility(tree: &GameTree, profile: &[[f64; N_ACTIONS]], node: usize) -> f64 {
+    match &tree.nodes[node] {
+        Node::Terminal { utility } => *utility,
+        Node::Chance { outcomes } => outcomes
+            .iter()
+            .map(|&(pr, child)| pr * node_utility(tree, profile, child))
+            .sum(),
+        Node::Decision {
+            info_set, children, ..
+        } => children
+            .iter()
+            .zip(profile[*info_set].iter())
+            .filter_map(|(child, &pr)| child.map(|child| pr * node_utility(tree, profile, child)))
+            .sum(),
+    }
+}
+
+// Probability of reaching each information set when both players follow `profile`
+pub fn reach_probabilities(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> Vec<f64> {
+    let mut reach = vec![0.0; tree.info_sets.len()];
+    collect_reach(tree, profile, tree.root, 1.0, &mut reach);
+    reach
+}
+
+fn collect_reach(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    node: usize,
+    pr: f64,
+    reach: &mut [f64],
+) {
+    match &tree.nodes[node] {
+        Node::Terminal { .. } => {}
+        Node::Chance { outcomes } => {
+            for &(child_pr, child) in outcomes {
+                collect_reach(tree, profile, child, pr * child_pr, reach);
+            }
+        }
+        Node::Decision {
+            info_set, children, ..
+        } => {
+            reach[*info_set] += pr;
+
+            for (child, &action_pr) in children.iter().zip(profile[*info_set].iter()) {
+                if let Some(child) = *child {
+                    collect_reach(tree, profile, child, pr * action_pr, reach);
+                }
+            }
+        }
+    }
+}
+
+// A pure strategy of one player that maximises their utility while the opponent
+// keeps following a profile
+#[derive(Debug, Clone)]
+pub struct BestResponse {
+    pub value: f64,
+    // The action picked at every information set of the player, `None` for the opponent
+    pub actions: Vec<Option<usize>>,
+}
+
+pub fn best_response(tree: &GameTree, profile: &[[f64; N_ACTIONS]], player: usize) -> BestResponse {
+    let mut solver = BestResponseSolver::new(tree, profile, player);
+    let value = solver.value(tree.root);
+
+    let actions = tree
+        .info_sets
+        .iter()
+        .enumerate()
+        .map(|(i, info_set)| (info_set.key.player() == player).then(|| solver.action(i)))
+        .collect();
+
+    BestResponse { value, actions }
+}
+
+// How much a player gains on average by switching to a best response, zero at an
+// equilibrium
+pub fn exploitability(tree: &GameTree, profile: &[[f64; N_ACTIONS]]) -> f64 {
+    // The game is zero-sum, the values of the profile cancel out
+    let gain = best_response(tree, profile, 0).value + best_response(tree, profile, 1).value;
+
+    gain / 2.0
+}
+
+// The best action of an information set depends on the values below every node in
+// it, so both are computed lazily and cached
+struct BestResponseSolver<'a> {
+    tree: &'a GameTree,
+    profile: &'a [[f64; N_ACTIONS]],
+    player: usize,
+    // Probability of chance and the opponent reaching each node
+    reach_pr: Vec<f64>,
+    // Nodes of each information set of `player`
+    members: Vec<Vec<usize>>,
+    values: Vec<Option<f64>>,
+    actions: Vec<Option<usize>>,
+}
+
+impl<'a> BestResponseSolver<'a> {
+    fn new(
+        tree: &'a GameTree,
+        profile: &'a [[f64; N_ACTIONS]],
+        player: usize,
+    ) -> BestResponseSolver<'a> {
+        let mut solver = BestResponseSolver {
+            tree,
+            profile,
+            player,
+            reach_pr: vec![0.0; tree.nodes.len()],
+            members: vec![Vec::new(); tree.info_sets.len()],
+            values: vec![None; tree.nodes.len()],
+            actions: vec![None; tree.info_sets.len()],
+        };
+
+        solver.collect(tree.root, 1.0);
+        solver
+    }
+
+    fn collect(&mut self, node: usize, pr: f64) {
+        self.reach_pr[node] = pr;
+
+        match &self.tree.nodes[node] {
+            Node::Terminal { .. } => {}
+            Node::Chance { outcomes } => {
+                for &(child_pr, child) in outcomes {
+                    self.collect(child, pr * child_pr);
+                }
+            }
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                if *player == self.player {
+                    self.members[*info_set].push(node);
+                }
+
+                for (i, child) in children.iter().enumerate() {
+                    let Some(child) = *child else {
+                        continue;
+                    };
+
+                    let child_pr = if *player == self.player {
+                        1.0
+                    } else {
+                        self.profile[*info_set][i]
+                    };
+                    self.collect(child, pr * child_pr);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, node: usize) -> f64 {
+        if let Some(value) = self.values[node] {
+            return value;
+        }
+
+        let value = match &self.tree.nodes[node] {
+            // Terminal utilities are given for player 1
+            Node::Terminal { utility } => {
+                if self.player == 0 {
+                    *utility
+                } else {
+                    -utility
+                }
+            }
+            Node::Chance { outcomes } => outcomes
+                .iter()
+                .map(|&(pr, child)| pr * self.value(child))
+                .sum(),
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                if *player == self.player {
+                    let action = self.action(*info_set);
+                    self.value(children[action].expect("best responses are legal"))
+                } else {
+                    children
+                        .iter()
+                        .zip(self.profile[*info_set].iter())
+                        .filter_map(|(child, &pr)| child.map(|child| pr * self.value(child)))
+                        .sum()
+                }
+            }
+        };
+
+        self.values[node] = Some(value);
+        value
+    }
+
+    // The legal action with the highest value summed over the nodes of the information
+    // set, weighted by how likely the opponent and chance make each node, ties go to
+    // the first one
+    fn action(&mut self, info_set: usize) -> usize {
+        if let Some(action) = self.actions[info_set] {
+            return action;
+        }
+
+        let mut action_values = [0.0; N_ACTIONS];
+        for node in self.members[info_set].clone() {
+            let Node::Decision { children, .. } = &self.tree.nodes[node] else {
+                unreachable!("information sets only hold decision nodes");
+            };
+
+            for (i, child) in children.iter().enumerate() {
+                if let Some(child) = *child {
+                    action_values[i] += self.reach_pr[node] * self.value(child);
+                }
+            }
+        }
+
+        let legal = self.tree.info_sets[info_set].legal;
+        let mut action = legal.iter().position(|&is_legal| is_legal).unwrap();
+        for (i, &value) in action_values.iter().enumerate() {
+            if legal[i] && value > action_values[action] {
+                action = i;
+            }
+        }
+
+        self.actions[info_set] = Some(action);
+        action
+    }
+}
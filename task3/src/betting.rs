@@ -0,0 +1,276 @@
+use crate::{CHANCE_ACTIONS, N_ACTIONS};
+
+// Bet size and raise cap of one betting round
+#[derive(Debug)]
+pub struct BettingRound {
+    pub bet_size: isize,
+    pub max_raises: usize,
+}
+
+// Betting structure of the game, the legal actions and the pot follow from it
+//
+// Player 1 opens every round and the players act in turn. A round is over once both
+// players have acted and neither has a bet to call, which is after two checks or a
+// call of a raise. The game stops after a fold or once every round is over
+#[derive(Debug)]
+pub struct BettingStructure {
+    pub ante: isize,
+    pub rounds: &'static [BettingRound],
+}
+
+// Up to two raises of 2 before the public card and two raises of 4 after it
+pub const BETTING: BettingStructure = BettingStructure {
+    ante: 1,
+    rounds: &[
+        BettingRound {
+            bet_size: 2,
+            max_raises: 2,
+        },
+        BettingRound {
+            bet_size: 4,
+            max_raises: 2,
+        },
+    ],
+};
+
+// Where the betting stands after a history
+//
+// 'c' puts in what the opponent has in the pot, which is a check when there is
+// nothing to call, and 'r' puts in the bet size of the round on top of it. `round`
+// is the round of the next action, it is past the last round once the game is over
+// without a fold, and `n_raises` and `n_actions` count the actions of that round
+#[derive(Debug, Clone, PartialEq)]
+pub struct BettingState {
+    pub player: usize,
+    pub round: usize,
+    pub contributions: [isize; 2],
+    pub n_raises: usize,
+    pub n_actions: usize,
+    pub folded: Option<usize>,
+}
+
+impl BettingState {
+    pub fn new(history: &str) -> BettingState {
+        let mut state = BettingState {
+            player: 0,
+            round: 0,
+            contributions: [BETTING.ante; 2],
+            n_raises: 0,
+            n_actions: 0,
+            folded: None,
+        };
+
+        for &op in history.as_bytes().iter().skip(2) {
+            state.apply(op as char);
+        }
+
+        state
+    }
+
+    // Play one action, the caller checks that it is legal
+    pub fn apply(&mut self, op: char) {
+        let opponent = 1 - self.player;
+
+        match op {
+            'r' => {
+                self.contributions[self.player] =
+                    self.contributions[opponent] + BETTING.rounds[self.round].bet_size;
+                self.n_raises += 1;
+            }
+            'c' => {
+                self.contributions[self.player] =
+                    self.contributions[self.player].max(self.contributions[opponent]);
+            }
+            _ => self.folded = Some(self.player),
+        }
+
+        self.player = opponent;
+        self.n_actions += 1;
+
+        if self.folded.is_none() && self.n_actions >= 2 && !self.facing_raise() {
+            self.round += 1;
+            self.player = 0;
+            self.n_raises = 0;
+            self.n_actions = 0;
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.folded.is_some() || self.round == BETTING.rounds.len()
+    }
+
+    // Calling is always allowed, raising below the cap of the round and folding only
+    // when there is a raise to call
+    pub fn legal_actions(&self) -> [bool; N_ACTIONS] {
+        if self.is_over() {
+            return [false; N_ACTIONS];
+        }
+
+        let can_raise = self.n_raises < BETTING.rounds[self.round].max_raises;
+        let can_fold = self.facing_raise();
+
+        CHANCE_ACTIONS.map(|action| match action {
+            "r" => can_raise,
+            "f" => can_fold,
+            _ => true,
+        })
+    }
+
+    // Whether the player to act has less in the pot than the opponent
+    pub fn facing_raise(&self) -> bool {
+        self.contributions[self.player] < self.contributions[1 - self.player]
+    }
+
+    pub fn pot(&self) -> isize {
+        self.contributions.iter().sum()
+    }
+}
+
+// Split the actions of a history into the betting rounds they belong to, the
+// last round is empty when it has just started
+pub fn split_rounds(history: &str) -> Vec<String> {
+    let mut state = BettingState::new("ii");
+    let mut rounds = vec![String::new()];
+
+    for &op in history.as_bytes().iter().skip(2) {
+        if let Some(actions) = rounds.last_mut() {
+            actions.push(op as char);
+        }
+
+        state.apply(op as char);
+
+        if state.round == rounds.len() && !state.is_over() {
+            rounds.push(String::new());
+        }
+    }
+
+    rounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_terminal, legal_actions};
+
+    fn terminal_histories(history: String, terminals: &mut Vec<String>) {
+        if is_terminal(&history) {
+            terminals.push(history);
+            return;
+        }
+
+        for (action, is_legal) in CHANCE_ACTIONS.iter().zip(legal_actions(&history)) {
+            if is_legal {
+                terminal_histories(format!("{}{}", history, action), terminals);
+            }
+        }
+    }
+
+    // Every terminal history of the game with its pot. Each round ends with "cc",
+    // "rc", "crc", "rrc" or "crrc" or with a fold of a raise, the pots count the antes
+    // of 1 and raises of 2 before the public card and of 4 after it
+    const TERMINALS: [(&str, isize); 49] = [
+        ("iicccc", 2),
+        ("iicccrc", 10),
+        ("iicccrf", 6),
+        ("iicccrrc", 18),
+        ("iicccrrf", 14),
+        ("iiccrc", 10),
+        ("iiccrf", 6),
+        ("iiccrrc", 18),
+        ("iiccrrf", 14),
+        ("iicrccc", 6),
+        ("iicrccrc", 14),
+        ("iicrccrf", 10),
+        ("iicrccrrc", 22),
+        ("iicrccrrf", 18),
+        ("iicrcrc", 14),
+        ("iicrcrf", 10),
+        ("iicrcrrc", 22),
+        ("iicrcrrf", 18),
+        ("iicrf", 4),
+        ("iicrrccc", 10),
+        ("iicrrccrc", 18),
+        ("iicrrccrf", 14),
+        ("iicrrccrrc", 26),
+        ("iicrrccrrf", 22),
+        ("iicrrcrc", 18),
+        ("iicrrcrf", 14),
+        ("iicrrcrrc", 26),
+        ("iicrrcrrf", 22),
+        ("iicrrf", 8),
+        ("iirccc", 6),
+        ("iirccrc", 14),
+        ("iirccrf", 10),
+        ("iirccrrc", 22),
+        ("iirccrrf", 18),
+        ("iircrc", 14),
+        ("iircrf", 10),
+        ("iircrrc", 22),
+        ("iircrrf", 18),
+        ("iirf", 4),
+        ("iirrccc", 10),
+        ("iirrccrc", 18),
+        ("iirrccrf", 14),
+        ("iirrccrrc", 26),
+        ("iirrccrrf", 22),
+        ("iirrcrc", 18),
+        ("iirrcrf", 14),
+        ("iirrcrrc", 26),
+        ("iirrcrrf", 22),
+        ("iirrf", 8),
+    ];
+
+    #[test]
+    fn generated_terminals_match_the_table() {
+        let mut terminals = Vec::new();
+        terminal_histories("ii".to_string(), &mut terminals);
+        terminals.sort();
+
+        let histories: Vec<&str> = TERMINALS.iter().map(|&(history, _)| history).collect();
+        assert_eq!(terminals, histories);
+
+        for (history, pot) in TERMINALS {
+            assert_eq!(BettingState::new(history).pot(), pot, "{}", history);
+        }
+    }
+
+    #[test]
+    fn raise_size_follows_the_round() {
+        assert_eq!(BettingState::new("iir").contributions, [3, 1]);
+        assert_eq!(BettingState::new("iirr").contributions, [3, 5]);
+        assert_eq!(BettingState::new("iiccr").contributions, [5, 1]);
+        assert_eq!(BettingState::new("iircr").contributions, [7, 3]);
+        assert_eq!(BettingState::new("iircrr").contributions, [7, 11]);
+    }
+
+    #[test]
+    fn raises_are_capped_in_every_round() {
+        assert_eq!(legal_actions("iir"), [true, true, true]);
+        assert_eq!(legal_actions("iirr"), [true, false, true]);
+        assert_eq!(legal_actions("iirrc"), [true, true, false]);
+        assert_eq!(legal_actions("iirrcrr"), [true, false, true]);
+        assert_eq!(legal_actions("iicccc"), [false; N_ACTIONS]);
+    }
+
+    #[test]
+    fn rounds_end_when_the_betting_is_closed() {
+        // A check or a raise that is still answered keeps the round going
+        for history in ["iic", "iir", "iicr", "iirr", "iicrr"] {
+            assert_eq!(BettingState::new(history).round, 0, "{}", history);
+        }
+        for history in ["iicc", "iirc", "iicrc", "iirrc", "iicrrc"] {
+            let state = BettingState::new(history);
+            assert_eq!((state.round, state.player), (1, 0), "{}", history);
+        }
+        assert_eq!(split_rounds("iicrcrc"), ["crc", "rc"]);
+        assert_eq!(split_rounds("iirrc"), ["rrc", ""]);
+    }
+
+    #[test]
+    fn folding_needs_a_raise_to_call() {
+        assert_eq!(legal_actions("ii"), [true, true, false]);
+        assert_eq!(legal_actions("iic"), [true, true, false]);
+        assert_eq!(legal_actions("iircc"), [true, true, false]);
+        assert_eq!(legal_actions("iircr"), [true, true, true]);
+    }
+}
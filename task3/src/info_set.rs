@@ -0,0 +1,234 @@
+use common::minimizer::{mask_illegal, uniform_strategy, RegretMinimizer};
+use common::pruning::{catch_up, Pruning};
+use common::purification::Purification;
+
+use crate::key::InfoSetKey;
+use crate::N_ACTIONS;
+
+// An information set of the game tree, its regrets and strategies live in
+// `InfoSetTable` at `index * N_ACTIONS`
+#[derive(Debug)]
+pub struct InformationSet {
+    pub key: InfoSetKey,
+    pub legal: [bool; N_ACTIONS],
+}
+
+impl InformationSet {
+    pub fn new(key: &InfoSetKey, legal: [bool; N_ACTIONS]) -> InformationSet {
+        InformationSet {
+            key: key.clone(),
+            legal,
+        }
+    }
+}
+
+// Flat storage of the regrets and strategies of every information set
+#[derive(Debug, Clone)]
+pub struct InfoSetTable {
+    pub minimizer: RegretMinimizer,
+    pub regret_sum: Vec<f64>,
+    // Regrets of the running iteration, folded into `regret_sum` by `next_strategy`
+    pub regret: Vec<f64>,
+    pub strategy_sum: Vec<f64>,
+    pub strategy: Vec<f64>,
+    pub reach_pr: Vec<f64>,
+    pub reach_pr_sum: Vec<f64>,
+    // Counterfactual value of every action summed over the CFR iterations
+    pub action_value_sum: Vec<f64>,
+    pub n_iterations: usize,
+    pub pruning: Option<Pruning>,
+    // Iterations in a row every action has been pruned for
+    pub n_pruned: Vec<usize>,
+    // Information sets whose strategy `next_strategy` leaves as it is
+    pub locked: Vec<bool>,
+    // Nodes traversed by CFR over all the iterations
+    pub n_nodes_visited: usize,
+    pub legal: Vec<[bool; N_ACTIONS]>,
+}
+
+impl InfoSetTable {
+    pub fn new(info_sets: &[InformationSet]) -> InfoSetTable {
+        let n_info_sets = info_sets.len();
+        let legal: Vec<[bool; N_ACTIONS]> =
+            info_sets.iter().map(|info_set| info_set.legal).collect();
+
+        InfoSetTable {
+            minimizer: RegretMinimizer::default(),
+            regret_sum: vec![0.0; n_info_sets * N_ACTIONS],
+            regret: vec![0.0; n_info_sets * N_ACTIONS],
+            strategy_sum: vec![0.0; n_info_sets * N_ACTIONS],
+            strategy: legal.iter().flat_map(uniform_strategy).collect(),
+            reach_pr: vec![0.0; n_info_sets],
+            reach_pr_sum: vec![0.0; n_info_sets],
+            action_value_sum: vec![0.0; n_info_sets * N_ACTIONS],
+            n_iterations: 0,
+            pruning: None,
+            n_pruned: vec![0; n_info_sets * N_ACTIONS],
+            locked: vec![false; n_info_sets],
+            n_nodes_visited: 0,
+            legal,
+        }
+    }
+
+    pub fn with_minimizer(mut self, minimizer: RegretMinimizer) -> InfoSetTable {
+        self.minimizer = minimizer;
+        self
+    }
+
+    pub fn with_pruning(mut self, pruning: Option<Pruning>) -> InfoSetTable {
+        self.pruning = pruning;
+        self
+    }
+
+    // Play `strategy` at the information set on every iteration
+    pub fn lock(&mut self, info_set: usize, strategy: &[f64]) {
+        self.strategy[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS].copy_from_slice(strategy);
+        self.locked[info_set] = true;
+    }
+
+    pub fn strategy(&self, info_set: usize) -> &[f64] {
+        &self.strategy[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS]
+    }
+
+    // Add the updates collected during a block of deals
+    pub fn merge(&mut self, buffer: &RegretBuffer) {
+        self.regret
+            .iter_mut()
+            .zip(buffer.regret.iter())
+            .for_each(|(a, &b)| *a += b);
+
+        self.reach_pr
+            .iter_mut()
+            .zip(buffer.reach_pr.iter())
+            .for_each(|(a, &b)| *a += b);
+
+        self.action_value_sum
+            .iter_mut()
+            .zip(buffer.action_value.iter())
+            .for_each(|(a, &b)| *a += b);
+
+        self.n_nodes_visited += buffer.n_nodes_visited;
+    }
+
+    // Actions whose subtrees are skipped during the running iteration
+    pub fn pruned_actions(&self, info_set: usize) -> [bool; N_ACTIONS] {
+        let mut pruned = [false; N_ACTIONS];
+
+        if let Some(pruning) = self.pruning {
+            if !pruning.is_full_iteration(self.n_iterations) {
+                let range = info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS;
+                pruned
+                    .iter_mut()
+                    .zip(self.strategy[range.clone()].iter())
+                    .zip(self.regret_sum[range].iter())
+                    .for_each(|((x, &pr), &regret)| *x = pruning.skips(pr, regret));
+            }
+        }
+        pruned
+    }
+
+    pub fn next_strategy(&mut self) {
+        for i in 0..self.reach_pr.len() {
+            let reach_pr = self.reach_pr[i];
+            let range = i * N_ACTIONS..(i + 1) * N_ACTIONS;
+
+            self.strategy_sum[range.clone()]
+                .iter_mut()
+                .zip(self.strategy[range.clone()].iter())
+                .for_each(|(a, &b)| *a += reach_pr * b);
+
+            // An action that comes back from pruning makes up for the iterations it missed
+            let pruned = self.pruned_actions(i);
+            catch_up(
+                &mut self.regret[range.clone()],
+                &mut self.n_pruned[range.clone()],
+                &pruned,
+            );
+
+            let regret = &self.regret[range.clone()];
+            self.minimizer
+                .accumulate(&mut self.regret_sum[range.clone()], regret);
+            let strategy =
+                self.minimizer
+                    .strategy(&self.regret_sum[range.clone()], regret, &self.legal[i]);
+            if !self.locked[i] {
+                self.strategy[range].copy_from_slice(&strategy);
+            }
+            self.reach_pr_sum[i] += reach_pr;
+        }
+
+        self.reach_pr.iter_mut().for_each(|x| *x = 0.0);
+        self.regret.iter_mut().for_each(|x| *x = 0.0);
+        self.n_iterations += 1;
+    }
+
+    // Add a strategy played with the given reach probability to the average strategy
+    pub fn add_average(&mut self, info_set: usize, strategy: &[f64], reach_pr: f64) {
+        self.strategy_sum[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS]
+            .iter_mut()
+            .zip(strategy.iter())
+            .for_each(|(a, &b)| *a += reach_pr * b);
+        self.reach_pr_sum[info_set] += reach_pr;
+    }
+
+    // Average strategy of every information set, indexed like `GameTree::info_sets`
+    pub fn average_profile(&self) -> Vec<[f64; N_ACTIONS]> {
+        (0..self.reach_pr_sum.len())
+            .map(|i| self.get_average_strategy(i))
+            .collect()
+    }
+
+    // Average strategies cleaned up for exporting or playing them
+    pub fn purified_profile(&self, purification: Purification) -> Vec<[f64; N_ACTIONS]> {
+        self.average_profile()
+            .into_iter()
+            .map(|strategy| purification.apply(strategy))
+            .collect()
+    }
+
+    // Average of the strategies played, weighted by the reach probability of the
+    // player, see `Purification` for cleaning it up
+    pub fn get_average_strategy(&self, info_set: usize) -> [f64; N_ACTIONS] {
+        let mut strategy = [0.0; N_ACTIONS];
+        strategy
+            .copy_from_slice(&self.strategy_sum[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS]);
+        mask_illegal(&mut strategy, &self.legal[info_set]);
+
+        let total = strategy.iter().sum::<f64>();
+
+        if total > 0.0 {
+            strategy.map(|x| x / total)
+        } else {
+            uniform_strategy(&self.legal[info_set])
+        }
+    }
+}
+
+// Regret and reach probability accumulated during a single iteration, laid out
+// like `InfoSetTable`
+#[derive(Debug, Clone)]
+pub struct RegretBuffer {
+    pub regret: Vec<f64>,
+    pub reach_pr: Vec<f64>,
+    pub action_value: Vec<f64>,
+    pub n_nodes_visited: usize,
+}
+
+impl RegretBuffer {
+    pub fn new(n_info_sets: usize) -> RegretBuffer {
+        RegretBuffer {
+            regret: vec![0.0; n_info_sets * N_ACTIONS],
+            reach_pr: vec![0.0; n_info_sets],
+            action_value: vec![0.0; n_info_sets * N_ACTIONS],
+            n_nodes_visited: 0,
+        }
+    }
+
+    // Zero the buffer for the next iteration
+    pub fn clear(&mut self) {
+        self.regret.fill(0.0);
+        self.reach_pr.fill(0.0);
+        self.action_value.fill(0.0);
+        self.n_nodes_visited = 0;
+    }
+}
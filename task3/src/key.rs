@@ -0,0 +1,173 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::betting::{split_rounds, BettingState};
+use crate::{card_rank, CHANCE_ACTIONS, RANKS};
+
+// Key of an information set: what the player to act knows about the game
+//
+// Cards are stored by rank because suits do not matter, it is shown as
+// `KJ iicr/c`, the private card followed by the public cards and the
+// actions of every round separated by '/'
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InfoSetKey {
+    pub card: usize,
+    pub public_cards: Vec<usize>,
+    pub rounds: Vec<String>,
+}
+
+impl InfoSetKey {
+    pub fn new(card: isize, public_card: isize, history: &str) -> InfoSetKey {
+        let public_cards = if public_card == -1 {
+            Vec::new()
+        } else {
+            vec![card_rank(public_card) as usize]
+        };
+
+        InfoSetKey {
+            card: card_rank(card) as usize,
+            public_cards,
+            rounds: split_rounds(history),
+        }
+    }
+
+    // Player 1 opens every round and the players act in turn, so the actions of the
+    // current round tell who is to act
+    pub fn player(&self) -> usize {
+        self.rounds.last().map_or(0, |actions| actions.len() % 2)
+    }
+
+    // The history the key was built from, e.g. `iicrc` for `KJ iicr/c`
+    pub fn history(&self) -> String {
+        format!("ii{}", self.rounds.concat())
+    }
+}
+
+impl fmt::Display for InfoSetKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", RANKS[self.card])?;
+        for &card in &self.public_cards {
+            write!(f, "{}", RANKS[card])?;
+        }
+
+        write!(f, " ii{}", self.rounds.join("/"))
+    }
+}
+
+impl FromStr for InfoSetKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<InfoSetKey, String> {
+        let (cards, history) = s
+            .split_once(' ')
+            .ok_or_else(|| format!("missing history in information set key '{}'", s))?;
+
+        let ranks = cards
+            .chars()
+            .map(|c| {
+                RANKS
+                    .iter()
+                    .position(|&rank| rank.starts_with(c))
+                    .ok_or_else(|| format!("unknown card '{}' in information set key '{}'", c, s))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+
+        let (&card, public_cards) = ranks
+            .split_first()
+            .ok_or_else(|| format!("missing private card in information set key '{}'", s))?;
+
+        let actions = history
+            .strip_prefix("ii")
+            .ok_or_else(|| format!("history of '{}' does not start with 'ii'", s))?;
+
+        let rounds: Vec<String> = actions.split('/').map(|round| round.to_string()).collect();
+
+        if rounds.len() != public_cards.len() + 1 {
+            return Err(format!(
+                "'{}' has {} rounds for {} public cards",
+                s,
+                rounds.len(),
+                public_cards.len()
+            ));
+        }
+
+        if let Some(op) = actions
+            .chars()
+            .find(|&op| op != '/' && !CHANCE_ACTIONS.contains(&op.to_string().as_str()))
+        {
+            return Err(format!("unknown action '{}' in '{}'", op, s));
+        }
+
+        // The actions must be legal one after the other and leave a player to act
+        let history = format!("ii{}", rounds.concat());
+        let mut state = BettingState::new("ii");
+        for op in history.chars().skip(2) {
+            let action = CHANCE_ACTIONS
+                .iter()
+                .position(|&name| name.starts_with(op))
+                .expect("the actions are known");
+            if !state.legal_actions()[action] {
+                return Err(format!("illegal action '{}' in '{}'", op, s));
+            }
+            state.apply(op);
+        }
+
+        if state.is_over() {
+            return Err(format!("the hand is over after '{}'", s));
+        }
+
+        if rounds != split_rounds(&history) {
+            return Err(format!(
+                "the rounds of '{}' do not end where the betting does",
+                s
+            ));
+        }
+
+        Ok(InfoSetKey {
+            card,
+            public_cards: public_cards.to_vec(),
+            rounds,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::GameTree;
+
+    #[test]
+    fn every_key_of_the_tree_reads_back() {
+        for info_set in &GameTree::new().info_sets {
+            let text = info_set.key.to_string();
+            assert_eq!(text.parse(), Ok(info_set.key.clone()), "{}", text);
+        }
+    }
+
+    #[test]
+    fn illegal_histories_are_rejected() {
+        for key in [
+            // The round is over without the public card, or the other way round
+            "J iicc",
+            "JQ iic/c",
+            "JQ iic/",
+            // A raise of the first round is not answered yet
+            "JQ iir/",
+            "JQ iicr/c",
+            "JQ iirr/",
+            // The third raise of a round
+            "J iirrr",
+            "JQ iicc/rrr",
+            // Folding with nothing to call
+            "J iif",
+            "J iicf",
+            // Nobody is left to act
+            "J iirf",
+            "JQ iicc/cc",
+            "JQ iicc/rc",
+        ] {
+            assert!(key.parse::<InfoSetKey>().is_err(), "{}", key);
+        }
+        assert!("JQ iirrc/".parse::<InfoSetKey>().is_ok());
+    }
+}
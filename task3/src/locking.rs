@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use crate::key::InfoSetKey;
+use crate::tree::GameTree;
+use crate::N_ACTIONS;
+
+// A strategy forced on an information set, e.g. `--lock "J ii=0,1,0"` always raises
+// the Jack when opening the first round
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lock {
+    pub key: InfoSetKey,
+    pub strategy: [f64; N_ACTIONS],
+}
+
+impl Lock {
+    // Index of the locked information set in `GameTree::info_sets`, the strategy may
+    // only play legal actions
+    pub fn info_set(&self, tree: &GameTree) -> Result<usize, String> {
+        let i = tree
+            .info_sets
+            .iter()
+            .position(|info_set| info_set.key == self.key)
+            .ok_or_else(|| format!("unknown information set '{}'", self.key))?;
+
+        let plays_illegal = self
+            .strategy
+            .iter()
+            .zip(tree.info_sets[i].legal.iter())
+            .any(|(&x, &is_legal)| x > 0.0 && !is_legal);
+
+        if plays_illegal {
+            return Err(format!("lock of '{}' plays an illegal action", self.key));
+        }
+        Ok(i)
+    }
+}
+
+impl FromStr for Lock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Lock, String> {
+        let (key, probabilities) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected KEY=P1,P2,P3 in lock '{}'", s))?;
+
+        let probabilities = probabilities
+            .split(',')
+            .map(|x| x.trim().parse::<f64>().ok().filter(|&x| x >= 0.0))
+            .collect::<Option<Vec<f64>>>()
+            .filter(|probabilities| probabilities.len() == N_ACTIONS)
+            .ok_or_else(|| {
+                format!(
+                    "expected {} non-negative probabilities in lock '{}'",
+                    N_ACTIONS, s
+                )
+            })?;
+
+        let total = probabilities.iter().sum::<f64>();
+        if total <= 0.0 {
+            return Err(format!("the probabilities of lock '{}' sum to zero", s));
+        }
+
+        let mut strategy = [0.0; N_ACTIONS];
+        strategy
+            .iter_mut()
+            .zip(probabilities.iter())
+            .for_each(|(a, &b)| *a = b / total);
+
+        Ok(Lock {
+            key: key.trim().parse()?,
+            strategy,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_normalises_locks() {
+        let lock: Lock = "KQ iirc/r=1,3,0".parse().unwrap();
+
+        assert_eq!(lock.key.to_string(), "KQ iirc/r");
+        assert_eq!(lock.strategy, [0.25, 0.75, 0.0]);
+        assert!("J ii".parse::<Lock>().is_err());
+        assert!("J ii=1,0".parse::<Lock>().is_err());
+        assert!("J ii=0,0,0".parse::<Lock>().is_err());
+        assert!("X ii=1,0,0".parse::<Lock>().is_err());
+    }
+
+    #[test]
+    fn locks_only_play_legal_actions() {
+        let tree = GameTree::new();
+
+        assert!("J ii=0,1,0"
+            .parse::<Lock>()
+            .unwrap()
+            .info_set(&tree)
+            .is_ok());
+        // Both raises of the round are made
+        assert!("J iirr=0,1,0"
+            .parse::<Lock>()
+            .unwrap()
+            .info_set(&tree)
+            .is_err());
+    }
+}
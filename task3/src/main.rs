@@ -1,120 +1,549 @@
-use lazy_static::lazy_static;
-use rand::Rng;
-use std::collections::HashMap;
-use std::fmt;
+mod baseline;
+mod best_response;
+mod betting;
+mod fictitious_play;
+mod info_set;
+mod key;
+mod locking;
+mod opponent;
+mod play;
+mod restricted;
+mod sequence_form;
+mod strategy_file;
+mod tree;
+mod workers;
 
-const N_ACTIONS: usize = 3;
-const N_CARDS: usize = 3;
-const CHANCE_ACTIONS: [&str; N_ACTIONS] = ["c", "r", "f"]; // There are three actions, 'c' => call, 'r' => raise, 'f' => fold
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::io;
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
 
-lazy_static! {
-    static ref PUBLIC_CARD: isize = rand::thread_rng().gen_range(0..=2); // Assuming card values range from 0 to 2
-}
+use common::agent::Agent;
+use common::minimizer::RegretMinimizer;
+use common::pruning::Pruning;
+use common::purification::Purification;
+use common::simulate::{duplicate_match, simulate, Estimate};
+use common::tournament::Tournament;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use baseline::{Baseline, BASELINES};
+use best_response::{expected_utility, exploitability, reach_probabilities};
+use betting::BettingState;
+use fictitious_play::fictitious_play;
+use info_set::{InfoSetTable, InformationSet, RegretBuffer};
+use locking::Lock;
+use opponent::{exploit, OpponentModel, OpponentSource};
+use play::play;
+use restricted::{evaluate, lock_model, restricted_tree};
+use tree::{GameTree, Node};
+use workers::with_workers;
 
-// Predefined terminal histories
-const TERMINALS: &[&str] = &[
-    "iicccc", "iicccrc", "iicccrf", "iicccf", "iiccrc", "iiccrrc", "iicccr", "iiccrrf", "iiccrf",
-    "iiccf", "iicrcc", "iicrcrc", "iicrcrf", "iicrcf", "iicrrc", "iicrrf", "iicrf", "iicf",
-    "iirccc", "iirccrc", "iirccrf", "iirccf", "iircrc", "iircrf", "iircf", "iirrcc", "iirrcf",
-    "iirrcc", "iirrf", "iirf", "iif",
-];
+pub const N_ACTIONS: usize = 3;
+pub const N_RANKS: usize = 3;
+pub const N_SUITS: usize = 2;
+pub const N_CARDS: usize = N_RANKS * N_SUITS; // Two suits of J, Q and K
+pub const RANKS: [&str; N_RANKS] = ["J", "Q", "K"];
+pub const CHANCE_ACTIONS: [&str; N_ACTIONS] = ["c", "r", "f"]; // There are three actions, 'c' => call, 'r' => raise, 'f' => fold
 
 fn main() {
-    let mut i_map: HashMap<String, InformationSet> = HashMap::new();
-    let n_iterations = 10000;
+    let options = Options::from_args();
+
+    if options.bench {
+        bench(&options);
+        return;
+    }
+
+    let tree = GameTree::new();
+
+    if let Some((a, b)) = &options.matchup {
+        match_session(&options, &tree, a, b);
+        return;
+    }
+
+    if !options.baselines.is_empty() && !options.strategies.is_empty() {
+        let agents: Vec<_> = options
+            .strategies
+            .iter()
+            .map(|path| {
+                let profile = strategy_file::load(&tree, path).unwrap_or_else(|error| {
+                    eprintln!("{}", error);
+                    process::exit(2);
+                });
+                (path.as_str(), profile)
+            })
+            .collect();
+        baseline_report(&options, &tree, &agents);
+        return;
+    }
+
+    if let Some(prefix) = &options.tournament {
+        tournament_session(&options, &tree, prefix);
+        return;
+    }
+
+    let mut table = InfoSetTable::new(&tree.info_sets)
+        .with_minimizer(options.minimizer)
+        .with_pruning(options.pruning);
+
+    for lock in &options.locks {
+        let info_set = lock.info_set(&tree).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
+        table.lock(info_set, &lock.strategy);
+    }
+
+    let opponent = options.opponent.as_ref().map(|source| {
+        let model = OpponentModel::load(&tree, source).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
+        (source, model)
+    });
+
+    if options.solver == Solver::SequenceForm {
+        let (value, profile) = sequence_form::solve(&tree).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        });
+
+        for (i, strategy) in profile.iter().enumerate() {
+            table.add_average(i, strategy, 1.0);
+        }
+
+        if options.play {
+            play_session(&options, &tree, &table);
+            return;
+        }
+
+        save_strategies(&options, &tree, &table);
+        display_results(&options, value, &tree, &table);
+
+        if let Some((source, model)) = &opponent {
+            opponent_report(&options, &tree, &table, source, model);
+            restricted_nash_report(&options, &tree, source, model);
+        }
+
+        if !options.baselines.is_empty() {
+            let profile = table.purified_profile(options.purification);
+            baseline_report(&options, &tree, &[("the trained strategy", profile)]);
+        }
+        return;
+    }
+
+    let n_iterations = options.n_iterations;
     let mut expected_game_value = 0.0;
 
-    for i in 0..n_iterations {
-        expected_game_value += cfr(&mut i_map, "", -1, -1, 1.0, 1.0, 1.0, 0);
+    with_workers(&tree, options.n_threads, |workers| {
+        for i in 0..n_iterations {
+            match options.solver {
+                Solver::Cfr => {
+                    expected_game_value += workers.chance_util(&mut table);
+
+                    table.next_strategy();
 
-        for v in i_map.values_mut() {
-            v.next_strategy();
+                    // The progress would bury the first hand
+                    if !options.play {
+                        println!(
+                            "iteration {}, expected game value: {}",
+                            i,
+                            expected_game_value / n_iterations as f64
+                        );
+                    }
+                }
+                Solver::FictitiousPlay => fictitious_play(&tree, &mut table),
+                Solver::SequenceForm => unreachable!("the sequence form is solved at once"),
+            }
+
+            if options.report_every > 0 && (i + 1) % options.report_every == 0 {
+                println!(
+                    "iteration {}, exploitability: {}",
+                    i + 1,
+                    exploitability(&tree, &table.average_profile())
+                );
+            }
         }
+    });
+
+    if options.play {
+        play_session(&options, &tree, &table);
+        return;
+    }
+
+    // Fictitious play only keeps the average profile, its value is computed exactly
+    let expected_game_value = match options.solver {
+        Solver::Cfr => expected_game_value / n_iterations as f64,
+        _ => expected_utility(&tree, &table.average_profile()),
+    };
+    save_strategies(&options, &tree, &table);
+    display_results(&options, expected_game_value, &tree, &table);
+
+    if options.pruning.is_some() && options.solver == Solver::Cfr {
+        // Without pruning every node below the deal is visited once per iteration
         println!(
-            "iteration {}, expected game value: {}",
-            i,
-            expected_game_value / n_iterations as f64
+            "nodes visited per iteration: {:.1} with pruning, {} without",
+            table.n_nodes_visited as f64 / n_iterations as f64,
+            tree.nodes.len() - 1
         );
     }
 
-    expected_game_value /= n_iterations as f64;
-    display_results(expected_game_value, &i_map);
+    if options.purification_report {
+        purification_report(&tree, &table);
+    }
+
+    if options.info_set_report {
+        info_set_report(&tree, &table);
+    }
+
+    if !options.locks.is_empty() {
+        node_locking_report(&options, &tree, &table);
+    }
+
+    if let Some((source, model)) = &opponent {
+        opponent_report(&options, &tree, &table, source, model);
+        restricted_nash_report(&options, &tree, source, model);
+    }
+
+    if !options.baselines.is_empty() {
+        let profile = table.purified_profile(options.purification);
+        baseline_report(&options, &tree, &[("the trained strategy", profile)]);
+    }
+
+    if options.n_simulated_hands > 0 {
+        let mut rng = StdRng::seed_from_u64(options.seed);
+        let profile = table.purified_profile(options.purification);
+        let mean = simulate(&tree, &profile, options.n_simulated_hands, &mut rng);
+
+        println!(
+            "\n{} simulated hands with seed {}",
+            options.n_simulated_hands, options.seed
+        );
+        for (player, value) in mean.iter().enumerate() {
+            println!("player {} mean winnings: {}", player + 1, value);
+        }
+    }
 }
 
-// The CFR (Counter Factual Regret Minimization) algorithm
-pub fn cfr(
-    i_map: &mut HashMap<String, InformationSet>,
-    history: &str,
-    card_1: isize,
-    card_2: isize,
-    pr_1: f64,
-    pr_2: f64,
-    pr_c: f64,
-    bet: isize,
-) -> f64 {
-    if is_chance_node(history) {
-        return chance_util(i_map);
+// Algorithm used to train the strategies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Solver {
+    Cfr,
+    FictitiousPlay,
+    SequenceForm,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Solver, String> {
+        match s {
+            "cfr" => Ok(Solver::Cfr),
+            "fp" => Ok(Solver::FictitiousPlay),
+            "lp" => Ok(Solver::SequenceForm),
+            _ => Err(format!("unknown solver {}, expected cfr, fp or lp", s)),
+        }
     }
+}
+
+// Command line options, e.g. `cargo run -p task3 -- --threads 4`
+//
+// `--solver fp --iterations 1000 --report 10` runs fictitious play instead of CFR and
+// prints the exploitability every 10 iterations, `--solver lp` solves the sequence-form
+// linear program exactly. `--purify argmax` cleans up the average strategies before
+// they are shown and `--purification-report` compares the options. `--info-set-report`
+// lists the CFR statistics of every information set. `--minimizer rm+` picks how CFR
+// turns the regrets into strategies and `--prune 10` skips the actions with a regret
+// below `--prune-threshold`, traversing the whole tree every 10 iterations.
+// `--lock "J ii=0,1,0"` makes CFR always play the given strategy at an information set,
+// it can be repeated. `--opponent model.txt` or `--opponent-log hands.txt` reads an
+// opponent model and reports what a best response against it wins, `--rnr 0,0.5,1`
+// adds restricted Nash responses to it for each probability of following the model.
+// `--play` deals hands between you and the trained strategy instead of printing it.
+// `--simulate 10000 --seed 7` plays
+// 10000 hands between the purified strategies, the same seed replays the same hands.
+// `--save leduc.txt` writes the purified strategies to a file and `--match a.txt
+// --against b.txt --deals 10000` plays two saved files against each other instead of
+// training. `--baselines` plays the trained strategy against every `Baseline` bot,
+// or the files given with `--strategy a.txt --strategy b.txt` without training,
+// `--baseline king --baseline strength:0.7` picks the bots instead.
+// `--tournament results` plays every pair of `--strategy` files and writes their
+// ratings, winnings and win rates to results-ratings.csv, results-winnings.md and so on
+pub struct Options {
+    pub n_threads: usize,
+    pub bench: bool,
+    pub solver: Solver,
+    pub minimizer: RegretMinimizer,
+    pub pruning: Option<Pruning>,
+    pub locks: Vec<Lock>,
+    pub opponent: Option<OpponentSource>,
+    pub rnr: Vec<f64>,
+    pub n_iterations: usize,
+    pub report_every: usize,
+    pub purification: Purification,
+    pub purification_report: bool,
+    pub info_set_report: bool,
+    pub play: bool,
+    pub n_simulated_hands: usize,
+    pub seed: u64,
+    pub save: Option<String>,
+    pub matchup: Option<(String, String)>,
+    pub n_deals: usize,
+    pub baselines: Vec<Baseline>,
+    pub strategies: Vec<String>,
+    pub tournament: Option<String>,
+}
+
+impl Options {
+    pub fn from_args() -> Options {
+        let args: Vec<String> = std::env::args().collect();
+
+        let n_threads = arg_value::<usize>(&args, "--threads")
+            .filter(|&n| n > 0)
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+
+        let bench = args.iter().any(|arg| arg == "--bench");
+
+        let solver = arg_value::<String>(&args, "--solver")
+            .map_or(Ok(Solver::Cfr), |name| name.parse())
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        let minimizer = arg_value::<String>(&args, "--minimizer")
+            .map_or(Ok(RegretMinimizer::default()), |name| name.parse())
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        let pruning = arg_value::<usize>(&args, "--prune")
+            .filter(|&interval| interval > 0)
+            .map(|interval| Pruning {
+                threshold: arg_value(&args, "--prune-threshold")
+                    .unwrap_or(Pruning::default().threshold),
+                interval,
+            });
+
+        if let Some(Err(error)) = pruning.map(|pruning| pruning.check(minimizer)) {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
 
-    if is_terminal(history) {
-        return terminal_util(history, card_1, card_2) as f64;
+        let locks = arg_values::<String>(&args, "--lock")
+            .iter()
+            .map(|lock| lock.parse())
+            .collect::<Result<Vec<Lock>, String>>()
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        if !locks.is_empty() && solver != Solver::Cfr {
+            eprintln!("locking information sets needs --solver cfr");
+            process::exit(2);
+        }
+
+        let opponent = arg_value(&args, "--opponent")
+            .map(OpponentSource::Table)
+            .or_else(|| arg_value(&args, "--opponent-log").map(OpponentSource::Log));
+
+        let rnr = arg_value::<String>(&args, "--rnr")
+            .map_or(Ok(Vec::new()), |list| {
+                list.split(',')
+                    .map(|p| {
+                        p.trim()
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|p| (0.0..=1.0).contains(p))
+                    })
+                    .collect::<Option<Vec<f64>>>()
+                    .ok_or_else(|| {
+                        format!("expected probabilities between 0 and 1 in --rnr {}", list)
+                    })
+            })
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        if !rnr.is_empty() && opponent.is_none() {
+            eprintln!("the restricted Nash response needs --opponent or --opponent-log");
+            process::exit(2);
+        }
+
+        let matchup = arg_value::<String>(&args, "--match").map(|a| {
+            let b = arg_value(&args, "--against").unwrap_or_else(|| {
+                eprintln!("--match needs a second strategy file with --against");
+                process::exit(2);
+            });
+            (a, b)
+        });
+
+        let strategies: Vec<String> = arg_values(&args, "--strategy");
+        let tournament = arg_value::<String>(&args, "--tournament");
+
+        if tournament.is_some() && strategies.len() < 2 {
+            eprintln!("the tournament needs at least two --strategy files");
+            process::exit(2);
+        }
+
+        let mut baselines = arg_values::<String>(&args, "--baseline")
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<Baseline>, String>>()
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+        if baselines.is_empty() && args.iter().any(|arg| arg == "--baselines") {
+            baselines = BASELINES.to_vec();
+        }
+
+        let purification = arg_value::<String>(&args, "--purify")
+            .map_or(Ok(Purification::default()), |name| name.parse())
+            .unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+
+        Options {
+            n_threads,
+            bench,
+            solver,
+            minimizer,
+            pruning,
+            locks,
+            opponent,
+            rnr,
+            n_iterations: arg_value(&args, "--iterations").unwrap_or(10000),
+            report_every: arg_value(&args, "--report").unwrap_or(0),
+            purification,
+            purification_report: args.iter().any(|arg| arg == "--purification-report"),
+            info_set_report: args.iter().any(|arg| arg == "--info-set-report"),
+            play: args.iter().any(|arg| arg == "--play"),
+            n_simulated_hands: arg_value(&args, "--simulate").unwrap_or(0),
+            seed: arg_value(&args, "--seed").unwrap_or(0),
+            save: arg_value(&args, "--save"),
+            matchup,
+            n_deals: arg_value(&args, "--deals")
+                .filter(|&n| n > 0)
+                .unwrap_or(10000),
+            baselines,
+            strategies,
+            tournament,
+        }
+    }
+}
+
+// Parse the value following a flag, e.g. `4` in `--threads 4`
+//
+// A flag without a value or with one that does not parse exits with status 2
+fn arg_value<T: FromStr>(args: &[String], name: &str) -> Option<T>
+where
+    T::Err: Display,
+{
+    let i = args.iter().position(|arg| arg == name)?;
+    let Some(value) = args.get(i + 1) else {
+        eprintln!("{} needs a value", name);
+        process::exit(2);
+    };
+    Some(parse_arg(name, value))
+}
+
+// Parse the values following every occurrence of a flag, e.g. `--lock A --lock B`
+fn arg_values<T: FromStr>(args: &[String], name: &str) -> Vec<T>
+where
+    T::Err: Display,
+{
+    if args.last().is_some_and(|arg| arg == name) {
+        eprintln!("{} needs a value", name);
+        process::exit(2);
     }
+    args.windows(2)
+        .filter(|pair| pair[0] == name)
+        .map(|pair| parse_arg(name, &pair[1]))
+        .collect()
+}
 
-    let n = history.len();
-    let is_player_1 = n % 2 == 0;
+fn parse_arg<T: FromStr>(name: &str, value: &str) -> T
+where
+    T::Err: Display,
+{
+    value.parse().unwrap_or_else(|error| {
+        eprintln!("invalid value '{}' for {}: {}", value, name, error);
+        process::exit(2);
+    })
+}
 
-    let (key, mut info_set) =
-        get_info_set(i_map, if is_player_1 { card_1 } else { card_2 }, history);
+// The CFR (Counter Factual Regret Minimization) algorithm, returns the utility of player 1
+//
+// Strategies are only read from `table`, all updates go into `buffer` so that
+// several deals can be traversed at the same time
+pub fn cfr(
+    tree: &GameTree,
+    table: &InfoSetTable,
+    buffer: &mut RegretBuffer,
+    node: usize,
+    pr_1: f64,
+    pr_2: f64,
+    pr_c: f64,
+) -> f64 {
+    buffer.n_nodes_visited += 1;
 
-    let strategy = &info_set.strategy;
+    let (player, info_set, children) = match &tree.nodes[node] {
+        Node::Terminal { utility } => return *utility,
+        Node::Chance { outcomes } => {
+            return outcomes
+                .iter()
+                .map(|&(pr, child)| pr * cfr(tree, table, buffer, child, pr_1, pr_2, pr_c * pr))
+                .sum();
+        }
+        Node::Decision {
+            player,
+            info_set,
+            children,
+        } => (*player, *info_set, children),
+    };
 
-    info_set.reach_pr += if is_player_1 { pr_1 } else { pr_2 };
+    let is_player_1 = player == 0;
+    let strategy = table.strategy(info_set);
+    let pruned = table.pruned_actions(info_set);
 
     let mut action_utils = [0.0; N_ACTIONS];
 
-    for (i, action) in CHANCE_ACTIONS.iter().enumerate() {
-        if action == &"r" && bet == 4 {
+    for (i, child) in children.iter().enumerate() {
+        // A pruned action is never played, so the utility of the node does not need it
+        let Some(child) = child.filter(|_| !pruned[i]) else {
             continue;
-        }
+        };
 
-        let next_history = format!("{}{}", history, action);
         if is_player_1 {
-            action_utils[i] = -1.0
-                * cfr(
-                    i_map,
-                    &next_history,
-                    card_1,
-                    card_2,
-                    pr_1 * strategy[i],
-                    pr_2,
-                    pr_c,
-                    bet + if action == &"r" { 2 } else { 0 },
-                );
+            action_utils[i] = cfr(tree, table, buffer, child, pr_1 * strategy[i], pr_2, pr_c);
         } else {
-            action_utils[i] = -1.0
-                * cfr(
-                    i_map,
-                    &next_history,
-                    card_1,
-                    card_2,
-                    pr_1,
-                    pr_2 * strategy[i],
-                    pr_c,
-                    bet + if action == &"r" { 2 } else { 0 },
-                );
+            action_utils[i] = -cfr(tree, table, buffer, child, pr_1, pr_2 * strategy[i], pr_c);
         }
     }
 
-    let util = action_utils
-        .iter()
-        .zip(strategy.iter())
-        .map(|(&x, &y)| x * y)
-        .sum();
-    let regrets: Vec<f64> = action_utils
-        .iter()
-        .zip(strategy.iter())
-        .map(|(&_x, &_y)| _x - util)
-        .collect();
+    let traversed = |i: &usize| children[*i].is_some() && !pruned[*i];
+
+    // The node is worth the strategy renormalised over the actions that were traversed
+    let traversed_pr = (0..N_ACTIONS)
+        .filter(traversed)
+        .map(|i| strategy[i])
+        .sum::<f64>();
+    let util = (0..N_ACTIONS)
+        .filter(traversed)
+        .map(|i| action_utils[i] * strategy[i] / traversed_pr)
+        .sum::<f64>();
+    let mut regrets = [0.0; N_ACTIONS];
+    for i in (0..N_ACTIONS).filter(traversed) {
+        regrets[i] = action_utils[i] - util;
+    }
 
     let (pr_1_factor, pr_2_factor) = if is_player_1 {
         (pr_2, pr_c)
@@ -122,267 +551,1077 @@ pub fn cfr(
         (pr_1, pr_c)
     };
 
-    info_set
-        .regret_sum
+    buffer.reach_pr[info_set] += if is_player_1 { pr_1 } else { pr_2 };
+
+    buffer.regret[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS]
         .iter_mut()
         .zip(regrets.iter())
         .for_each(|(a, &b)| *a += pr_1_factor * pr_2_factor * b);
 
-    i_map.insert(key, info_set);
+    // An illegal or pruned action has no value in this iteration
+    for i in (0..N_ACTIONS).filter(traversed) {
+        buffer.action_value[info_set * N_ACTIONS + i] +=
+            pr_1_factor * pr_2_factor * action_utils[i];
+    }
 
-    util
+    if is_player_1 {
+        util
+    } else {
+        -util
+    }
 }
 
-// Determine if we are at the natural moves (random chance nodes)
-fn is_chance_node(history: &str) -> bool {
-    history.is_empty()
+// Determine if the public card has to be dealt, which happens once the first round is
+// closed by two checks or a called raise
+pub fn is_chance_node(history: &str, public_card: isize) -> bool {
+    let state = BettingState::new(history);
+    public_card == -1 && state.round == 1 && !state.is_over()
 }
 
-// Start the CFR algorithm by iterating through every combination of chance nodes
-fn chance_util(i_map: &mut HashMap<String, InformationSet>) -> f64 {
-    let mut expected_value = 0.0;
-    let n_possibilities = 6;
-    for i in 0..N_CARDS {
-        for j in 0..N_CARDS {
-            if i != j {
-                expected_value += cfr(
-                    i_map,
-                    "ii",
-                    i as isize,
-                    j as isize,
-                    1.0,
-                    1.0,
-                    1.0 / n_possibilities as f64,
-                    0,
-                );
-            }
-        }
+// Check if we have reached the terminal history
+pub fn is_terminal(history: &str) -> bool {
+    BettingState::new(history).is_over()
+}
+
+// Actions allowed after the given history, none once the game is over
+pub fn legal_actions(history: &str) -> [bool; N_ACTIONS] {
+    BettingState::new(history).legal_actions()
+}
+
+// Calculate the terminal utility of player 1
+pub fn terminal_util(history: &str, card_1: isize, card_2: isize, public_card: isize) -> f64 {
+    let state = BettingState::new(history);
+
+    if let Some(player) = state.folded {
+        // The player who folded loses what they put in the pot
+        let net = state.contributions[player] as f64;
+        return if player == 0 { -net } else { net };
+    }
+
+    // Both players put the same amount in the pot before the showdown
+    let net = state.pot() as f64 / 2.0;
+
+    match hand_strength(card_1, public_card).cmp(&hand_strength(card_2, public_card)) {
+        Ordering::Greater => net,
+        Ordering::Less => -net,
+        // Both players hold the same rank, the pot is split
+        Ordering::Equal => 0.0,
     }
-    expected_value / n_possibilities as f64
 }
 
-// Check if we have reached the terminal history
-fn is_terminal(history: &str) -> bool {
-    TERMINALS.contains(&history)
+// A pair with the public card beats any other hand, otherwise the higher rank wins
+fn hand_strength(card: isize, public_card: isize) -> isize {
+    let rank = card_rank(card);
+
+    if rank == card_rank(public_card) {
+        N_RANKS as isize + rank
+    } else {
+        rank
+    }
+}
+
+pub fn card_rank(card: isize) -> isize {
+    card / N_SUITS as isize
 }
 
-// Calculate the terminal utility
-fn terminal_util(history: &str, card_1: isize, card_2: isize) -> f64 {
-    let card_val = [("J", 1), ("Q", 2), ("K", 3)];
+// Format an information set with the average strategy of its legal actions
+fn format_info_set(info_set: &InformationSet, strategy: [f64; N_ACTIONS]) -> String {
+    let strategies: Vec<String> = CHANCE_ACTIONS
+        .iter()
+        .zip(strategy.iter())
+        .zip(info_set.legal.iter())
+        .filter(|(_, &is_legal)| is_legal)
+        .map(|((action, x), _)| format!("{} {:03.2}", action, x))
+        .collect();
 
-    let n = history.len();
-    let card_player = if n % 2 == 0 { 1 } else { -1 };
+    format!("{} [{}]", info_set.key, strategies.join(", "))
+}
 
-    let net = get_pot(history, card_player) as f64;
+fn display_results(options: &Options, ev: f64, tree: &GameTree, table: &InfoSetTable) {
+    let profile = table.purified_profile(options.purification);
 
-    if history.ends_with('f') {
-        return -((card_player as f64) * net);
-    } else if card_player == 1 {
-        if card_1 == *PUBLIC_CARD {
-            return (card_player as f64) * net;
-        } else if card_2 == *PUBLIC_CARD {
-            return -((card_player as f64) * net);
-        } else {
-            let val_1 = card_val
-                .iter()
-                .find(|&&(s, _)| s == card_str(card_1 as usize))
-                .unwrap()
-                .1;
-            let val_2 = card_val
-                .iter()
-                .find(|&&(s, _)| s == card_str(card_2 as usize))
-                .unwrap()
-                .1;
-            return (card_player as f64) * (if val_1 > val_2 { 1.0 } else { -1.0 }) * net;
+    println!();
+    println!("==== notation ====");
+    println!("i => initial");
+    println!("/ => public card dealt");
+    println!("c => call");
+    println!("r => raise");
+    println!("f => fold");
+    println!("==================");
+    println!();
+
+    println!("player 1 expected value: {}", ev);
+    println!("player 2 expected value: {}", -ev);
+    println!(
+        "exploitability with purification {}: {}",
+        options.purification,
+        exploitability(tree, &profile)
+    );
+    println!();
+
+    let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
+
+    items.sort_by(|a, b| a.1.key.cmp(&b.1.key));
+
+    let (p1_items, p2_items) = items
+        .into_iter()
+        .partition::<Vec<_>, _>(|(_, info_set)| info_set.key.player() == 0);
+
+    println!("\nplayer 1 strategies:");
+    for (i, info_set) in p1_items {
+        println!("{}", format_info_set(info_set, profile[i]));
+    }
+
+    println!("\nplayer 2 strategies:");
+    for (i, info_set) in p2_items {
+        println!("{}", format_info_set(info_set, profile[i]));
+    }
+}
+
+// Write the purified average strategies to the `--save` file
+fn save_strategies(options: &Options, tree: &GameTree, table: &InfoSetTable) {
+    if let Some(path) = &options.save {
+        let profile = table.purified_profile(options.purification);
+        if let Err(error) = strategy_file::save(tree, &profile, path) {
+            eprintln!("{}", error);
+            process::exit(2);
         }
-    } else {
-        if card_2 == *PUBLIC_CARD {
-            return -((card_player as f64) * net);
-        } else if card_1 == *PUBLIC_CARD {
-            return (card_player as f64) * net;
-        } else {
-            let val_1 = card_val
-                .iter()
-                .find(|&&(s, _)| s == card_str(card_1 as usize))
-                .unwrap()
-                .1;
-            let val_2 = card_val
-                .iter()
-                .find(|&&(s, _)| s == card_str(card_2 as usize))
-                .unwrap()
-                .1;
-            return -((card_player as f64) * (if val_1 > val_2 { 1.0 } else { -1.0 }) * net);
+    }
+}
+
+// Play the strategy file `a` against `b` with duplicate dealing and report what `a`
+// wins per hand
+fn match_session(options: &Options, tree: &GameTree, a: &str, b: &str) {
+    let [profile_a, profile_b] = [a, b].map(|path| {
+        strategy_file::load(tree, path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(2);
+        })
+    });
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let samples = duplicate_match(tree, &profile_a, &profile_b, options.n_deals, &mut rng);
+    let estimate = Estimate::new(&samples);
+    let (low, high) = estimate.confidence_interval();
+
+    println!(
+        "{} against {}: {} duplicate deals ({} hands) with seed {}",
+        a,
+        b,
+        options.n_deals,
+        options.n_deals * 2,
+        options.seed
+    );
+    println!(
+        "{} mean winnings per hand: {:.6} (standard error {:.6})",
+        a, estimate.mean, estimate.std_error
+    );
+    println!("95% confidence interval: [{:.6}, {:.6}]", low, high);
+}
+
+// Round robin between the `--strategy` files, the ratings and the matrix of winnings
+// are printed and written as CSV and Markdown next to `prefix`
+fn tournament_session(options: &Options, tree: &GameTree, prefix: &str) {
+    let entries: Vec<_> = options
+        .strategies
+        .iter()
+        .map(|path| {
+            let profile = strategy_file::load(tree, path).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(2);
+            });
+            (path.clone(), profile)
+        })
+        .collect();
+
+    let tournament = round_robin(tree, &entries, options.n_deals, options.seed);
+
+    println!(
+        "round robin between {} strategies, {} duplicate deals per match with seed {}\n",
+        entries.len(),
+        options.n_deals,
+        options.seed
+    );
+    print!("{}", tournament.ratings_markdown());
+    println!("\nwinnings per hand of the row against the column:\n");
+    print!("{}", tournament.winnings_markdown());
+    println!("\nshare of the deals the row wins against the column:\n");
+    print!("{}", tournament.win_rates_markdown());
+
+    for (suffix, text) in [
+        ("ratings.csv", tournament.ratings_csv()),
+        ("ratings.md", tournament.ratings_markdown()),
+        ("winnings.csv", tournament.winnings_csv()),
+        ("winnings.md", tournament.winnings_markdown()),
+        ("win-rates.csv", tournament.win_rates_csv()),
+        ("win-rates.md", tournament.win_rates_markdown()),
+    ] {
+        let path = format!("{}-{}", prefix, suffix);
+        if let Err(error) = std::fs::write(&path, text) {
+            eprintln!("cannot write {}: {}", path, error);
+            process::exit(2);
         }
     }
 }
 
-// Calculate the amount of chipis in the pot based on the given game history
-fn get_pot(history: &str, turn: isize) -> isize {
-    let mut bet = [0, 0];
-    let mut prev = 1;
-    let mut player = 0;
-    let pot = 1;
-    let mut bet_round = 1;
-
-    for &op in history.as_bytes().iter().skip(2) {
-        match op as char {
-            'r' => {
-                bet[player] += prev - ((bet_round * 2) - bet[player]);
-                prev += 2;
-                bet_round += 1;
-            }
-            'c' => {
-                bet[player] += prev - bet[player];
-            }
-            _ => {}
+// Every pair of `entries` plays a duplicate match on the deals of `seed`
+fn round_robin(
+    tree: &GameTree,
+    entries: &[(String, Vec<[f64; N_ACTIONS]>)],
+    n_deals: usize,
+    seed: u64,
+) -> Tournament {
+    let names = entries.iter().map(|(name, _)| name.clone()).collect();
+
+    Tournament::new(names, |i, j| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        duplicate_match(tree, &entries[i].1, &entries[j].1, n_deals, &mut rng)
+    })
+}
+
+// Mean winnings per hand of every agent against each baseline bot over duplicate
+// deals, with the share of the deals it wins. Every match uses the same seed so the
+// agents face the same cards
+fn baseline_report(options: &Options, tree: &GameTree, agents: &[(&str, Vec<[f64; N_ACTIONS]>)]) {
+    for (name, profile) in agents {
+        println!(
+            "\n{} against the baselines, {} duplicate deals with seed {}:",
+            name, options.n_deals, options.seed
+        );
+        println!(
+            "{:<14} {:>10} {:>22} {:>10}",
+            "baseline", "winnings", "95% interval", "deals won"
+        );
+
+        for baseline in &options.baselines {
+            let mut rng = StdRng::seed_from_u64(options.seed);
+            let samples = duplicate_match(
+                tree,
+                profile,
+                &baseline.profile(tree),
+                options.n_deals,
+                &mut rng,
+            );
+            let estimate = Estimate::new(&samples);
+            let (low, high) = estimate.confidence_interval();
+            let n_won = samples.iter().filter(|&&x| x > 0.0).count();
+
+            println!(
+                "{:<14} {:>10.4} {:>22} {:>9.1}%",
+                baseline.to_string(),
+                estimate.mean,
+                format!("[{:.4}, {:.4}]", low, high),
+                100.0 * n_won as f64 / samples.len() as f64
+            );
         }
+    }
+}
+
+// Play against the average strategy after `--purification` in the terminal, the
+// deals and the actions of the bot come from `--seed`
+fn play_session(options: &Options, tree: &GameTree, table: &InfoSetTable) {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let profile = table.purified_profile(options.purification);
 
-        player = (player + 1) % 2;
+    match play(
+        tree,
+        &profile,
+        &mut rng,
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+    ) {
+        Ok(score) => println!("\nfinal score: {:+}", score),
+        Err(error) => eprintln!("{}", error),
     }
+}
+
+// Exploitability of the average strategies after each purification and how much the
+// purification adds to it
+fn purification_report(tree: &GameTree, table: &InfoSetTable) {
+    let profile = table.average_profile();
+    let base = exploitability(tree, &profile);
 
-    pot + if turn == -1 { bet[0] } else { bet[1] }
+    println!("\npurification exploitability:");
+    for purification in [
+        Purification::None,
+        Purification::Threshold(0.001),
+        Purification::Threshold(0.01),
+        Purification::Threshold(0.05),
+        Purification::Threshold(0.1),
+        Purification::TopK(2),
+        Purification::Argmax,
+    ] {
+        let purified: Vec<[f64; N_ACTIONS]> = profile
+            .iter()
+            .map(|&strategy| purification.apply(strategy))
+            .collect();
+        let value = exploitability(tree, &purified);
+
+        println!(
+            "{:<16} {:.6} ({:+.6})",
+            purification.to_string(),
+            value,
+            value - base
+        );
+    }
 }
 
-// Retrieve information set from dictionary
-fn get_info_set(
-    i_map: &mut HashMap<String, InformationSet>,
-    card: isize,
-    history: &str,
-) -> (String, InformationSet) {
-    let key = format!("{} {}", card_str(card as usize), history);
+// Reach probability, counterfactual value of every legal action, cumulative regret
+// and average strategy of each information set, the most reached ones first
+//
+// `reach` is the probability of the set under the average strategies and `own` the
+// reach probability of its player summed over its histories, `reach_pr_sum`. Both
+// `own` and the values are averaged over the CFR iterations, the values are weighted
+// by how likely the opponent and chance reach the set
+fn info_set_report(tree: &GameTree, table: &InfoSetTable) {
+    if table.n_iterations == 0 {
+        eprintln!("the information set report needs --solver cfr");
+        return;
+    }
+
+    let n_iterations = table.n_iterations as f64;
+    let reach = reach_probabilities(tree, &table.average_profile());
+    let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
+
+    items.sort_by(|a, b| {
+        reach[b.0]
+            .total_cmp(&reach[a.0])
+            .then_with(|| a.1.key.cmp(&b.1.key))
+    });
 
-    let info_set = i_map
-        .remove(&key)
-        .unwrap_or_else(|| InformationSet::new(&key));
+    println!("\ninformation set report:");
+    for (i, info_set) in items {
+        let range = i * N_ACTIONS..(i + 1) * N_ACTIONS;
+        let values: Vec<f64> = table.action_value_sum[range.clone()]
+            .iter()
+            .map(|x| x / n_iterations)
+            .collect();
 
-    (key, info_set)
+        println!(
+            "{} reach {:.4} own {:.4} value [{}] regret [{}] strategy [{}]",
+            info_set.key,
+            reach[i],
+            table.reach_pr_sum[i] / n_iterations,
+            format_actions(&values, &info_set.legal),
+            format_actions(&table.regret_sum[range], &info_set.legal),
+            format_actions(&table.get_average_strategy(i), &info_set.legal)
+        );
+    }
 }
 
-fn card_str(card: usize) -> &'static str {
-    let combs = ["J", "Q", "K"];
-    return combs[card];
+// Expected value of both players with the locked information sets against a CFR run
+// of the same length without them, e.g. how much player 2 wins when player 1 always
+// raises the Jack
+fn node_locking_report(options: &Options, tree: &GameTree, table: &InfoSetTable) {
+    let mut unlocked = InfoSetTable::new(&tree.info_sets)
+        .with_minimizer(options.minimizer)
+        .with_pruning(options.pruning);
+
+    with_workers(tree, options.n_threads, |workers| {
+        for _ in 0..options.n_iterations {
+            workers.chance_util(&mut unlocked);
+            unlocked.next_strategy();
+        }
+    });
+
+    let before = expected_utility(tree, &unlocked.average_profile());
+    let after = expected_utility(tree, &table.average_profile());
+
+    println!("\nnode locking report:");
+    for lock in &options.locks {
+        let info_set = lock
+            .info_set(tree)
+            .expect("the locks are checked before training");
+        println!(
+            "locked {} [{}]",
+            lock.key,
+            format_actions(&lock.strategy, &tree.info_sets[info_set].legal)
+        );
+    }
+    for (player, sign) in [(0, 1.0), (1, -1.0)] {
+        let has_locks = tree
+            .info_sets
+            .iter()
+            .zip(table.locked.iter())
+            .any(|(info_set, &locked)| locked && info_set.key.player() == player);
+
+        println!(
+            "player {}{} expected value: {:.6} unlocked, {:.6} locked ({:+.6})",
+            player + 1,
+            if has_locks { " (locked)" } else { "" },
+            sign * before,
+            sign * after,
+            sign * (after - before)
+        );
+    }
 }
 
-#[derive(Debug)]
-pub struct InformationSet {
-    pub key: String,
-    pub regret_sum: [f64; N_ACTIONS],
-    pub strategy_sum: [f64; N_ACTIONS],
-    pub strategy: [f64; N_ACTIONS],
-    pub reach_pr: f64,
-    pub reach_pr_sum: f64,
+// What the best response of the player left out of the opponent model wins against
+// it, compared with the shown equilibrium strategy, followed by the action the best
+// response picks at each information set
+fn opponent_report(
+    options: &Options,
+    tree: &GameTree,
+    table: &InfoSetTable,
+    source: &OpponentSource,
+    model: &OpponentModel,
+) {
+    let equilibrium = table.purified_profile(options.purification);
+
+    println!(
+        "\nexploiting the opponent {} ({} information sets):",
+        source,
+        model.strategies.len()
+    );
+    for player in model.exploiting_players(tree) {
+        let exploitation = exploit(tree, model, &equilibrium, player);
+
+        println!(
+            "player {} best response {:.6}, equilibrium {:.6} ({:+.6})",
+            player + 1,
+            exploitation.best_response,
+            exploitation.equilibrium,
+            exploitation.best_response - exploitation.equilibrium
+        );
+
+        let mut items = tree.info_sets.iter().enumerate().collect::<Vec<_>>();
+        items.sort_by(|a, b| a.1.key.cmp(&b.1.key));
+
+        for (i, info_set) in items {
+            if let Some(action) = exploitation.actions[i] {
+                println!("{} {}", info_set.key, CHANCE_ACTIONS[action]);
+            }
+        }
+    }
 }
 
-impl InformationSet {
-    pub fn new(key: &str) -> InformationSet {
-        InformationSet {
-            key: key.to_string(),
-            regret_sum: [0.0; N_ACTIONS],
-            strategy_sum: [0.0; N_ACTIONS],
-            strategy: [1.0 / N_ACTIONS as f64; N_ACTIONS],
-            reach_pr: 0.0,
-            reach_pr_sum: 0.0,
+// Restricted Nash response of the player left out of the opponent model for every
+// `--rnr` probability: what it wins against the model, what it wins against a best
+// response and how much less that is than the value of the game
+fn restricted_nash_report(
+    options: &Options,
+    tree: &GameTree,
+    source: &OpponentSource,
+    model: &OpponentModel,
+) {
+    if options.rnr.is_empty() {
+        return;
+    }
+
+    let [exploiter] = model.exploiting_players(tree)[..] else {
+        eprintln!("the restricted Nash response needs a model of only one player");
+        return;
+    };
+
+    let value = match sequence_form::solve(tree) {
+        Ok((value, _)) => value,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
         }
+    };
+    let game_value = if exploiter == 0 { value } else { -value };
+
+    println!(
+        "\nrestricted Nash response of player {} against the opponent {}:",
+        exploiter + 1,
+        source
+    );
+    println!(
+        "{:<6} {:>12} {:>12} {:>14}",
+        "p", "vs model", "worst case", "exploitability"
+    );
+    for &p in &options.rnr {
+        let restricted = restricted_tree(tree, model, p);
+        let mut table = InfoSetTable::new(&restricted.info_sets)
+            .with_minimizer(options.minimizer)
+            .with_pruning(options.pruning);
+        lock_model(&mut table, tree, model);
+
+        with_workers(&restricted, options.n_threads, |workers| {
+            for _ in 0..options.n_iterations {
+                workers.chance_util(&mut table);
+                table.next_strategy();
+            }
+        });
+
+        // The copies of the modelled information sets come after the original ones
+        let profile = &table.average_profile()[..tree.info_sets.len()];
+        let point = evaluate(tree, model, profile, exploiter);
+
+        println!(
+            "{:<6.2} {:>12.6} {:>12.6} {:>14.6}",
+            p,
+            point.against_model,
+            point.worst_case,
+            game_value - point.worst_case
+        );
     }
+}
+
+// Label the value of every legal action, e.g. `c 0.2500, r 0.7500`
+fn format_actions(values: &[f64], legal: &[bool; N_ACTIONS]) -> String {
+    CHANCE_ACTIONS
+        .iter()
+        .zip(values.iter())
+        .zip(legal.iter())
+        .filter(|(_, &is_legal)| is_legal)
+        .map(|((action, x), _)| format!("{} {:.4}", action, x))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Time the tree construction and the CFR iterations, e.g. `cargo run --release -p task3 -- --bench`
+fn bench(options: &Options) {
+    let n_iterations = 10000;
+
+    let start = Instant::now();
+    let tree = GameTree::new();
+    let build_time = start.elapsed();
+
+    println!(
+        "leduc: {} nodes, {} information sets, built in {:?}",
+        tree.nodes.len(),
+        tree.info_sets.len(),
+        build_time
+    );
 
-    pub fn next_strategy(&mut self) {
-        self.strategy_sum
-            .iter_mut()
-            .zip(self.strategy.iter())
-            .for_each(|(a, &b)| *a += self.reach_pr * b);
+    for pruning in [None, Some(options.pruning.unwrap_or_default())] {
+        let mut table = InfoSetTable::new(&tree.info_sets).with_pruning(pruning);
 
-        self.strategy = self.calc_strategy();
-        self.reach_pr_sum += self.reach_pr;
-        self.reach_pr = 0.0;
+        let start = Instant::now();
+        let n_threads = with_workers(&tree, options.n_threads, |workers| {
+            for _ in 0..n_iterations {
+                workers.chance_util(&mut table);
+                table.next_strategy();
+            }
+            workers.n_threads()
+        });
+        let train_time = start.elapsed();
+
+        println!(
+            "leduc{}: {} iterations on {} threads in {:?} ({:.2} µs/iteration, {:.1} nodes/iteration)",
+            if pruning.is_some() { " with pruning" } else { "" },
+            n_iterations,
+            n_threads,
+            train_time,
+            train_time.as_secs_f64() * 1e6 / n_iterations as f64,
+            table.n_nodes_visited as f64 / n_iterations as f64
+        );
     }
+}
 
-    fn calc_strategy(&self) -> [f64; N_ACTIONS] {
-        let strategy = self.make_positive(self.regret_sum);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use best_response::best_response;
+    use common::simulate::seat_profile;
+    use workers::Workers;
 
-        let total = strategy.iter().sum::<f64>();
+    #[test]
+    fn every_minimizer_lowers_exploitability() {
+        let tree = GameTree::new();
+        let uniform = exploitability(&tree, &InfoSetTable::new(&tree.info_sets).average_profile());
 
-        if total > 0.0 {
-            strategy.map(|x| x / total)
-        } else {
-            [1.0 / N_ACTIONS as f64; N_ACTIONS]
+        for minimizer in ["rm", "rm+", "prm+", "hedge"] {
+            let minimizer: RegretMinimizer = minimizer.parse().unwrap();
+            let mut table = InfoSetTable::new(&tree.info_sets).with_minimizer(minimizer);
+
+            let mut workers = Workers::serial(&tree);
+            for _ in 0..100 {
+                workers.chance_util(&mut table);
+                table.next_strategy();
+            }
+
+            let exploitability = exploitability(&tree, &table.average_profile());
+            assert!(
+                exploitability < uniform / 4.0,
+                "{}: {}",
+                minimizer,
+                exploitability
+            );
+            assert!(table
+                .strategy
+                .chunks(N_ACTIONS)
+                .zip(tree.info_sets.iter())
+                .all(|(strategy, info_set)| {
+                    strategy
+                        .iter()
+                        .zip(info_set.legal.iter())
+                        .all(|(&x, &is_legal)| is_legal || x == 0.0)
+                }));
         }
     }
 
-    fn get_average_strategy(&self) -> [f64; N_ACTIONS] {
-        let strategy = self.strategy_sum.map(|x| x / self.reach_pr_sum);
+    #[test]
+    fn pruning_skips_nodes_and_still_learns() {
+        let tree = GameTree::new();
+        let uniform = exploitability(&tree, &InfoSetTable::new(&tree.info_sets).average_profile());
+        let mut table = InfoSetTable::new(&tree.info_sets).with_pruning(Some(Pruning::default()));
 
-        let strategy = strategy.map(|x| if x < 0.001 { 0.0 } else { x });
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..300 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
 
-        let total = strategy.iter().sum::<f64>();
+        let nodes_per_iteration = table.n_nodes_visited as f64 / 300.0;
+        assert!(nodes_per_iteration < 0.5 * (tree.nodes.len() - 1) as f64);
 
-        if total > 0.0 {
-            strategy.map(|x| x / total)
-        } else {
-            [1.0 / N_ACTIONS as f64; N_ACTIONS]
+        let exploitability = exploitability(&tree, &table.average_profile());
+        assert!(exploitability < uniform / 4.0, "{}", exploitability);
+    }
+
+    #[test]
+    fn locked_information_sets_keep_their_strategy() {
+        let tree = GameTree::new();
+        let lock: Lock = "K ii=1,0,0".parse().unwrap();
+        let info_set = lock.info_set(&tree).unwrap();
+
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        table.lock(info_set, &lock.strategy);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..100 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+
+        assert_eq!(table.strategy(info_set), lock.strategy);
+        assert!((table.average_profile()[info_set][0] - 1.0).abs() < 1e-12);
+        assert!(
+            table.regret_sum[info_set * N_ACTIONS..(info_set + 1) * N_ACTIONS]
+                .iter()
+                .any(|&x| x != 0.0)
+        );
+    }
+
+    #[test]
+    fn best_response_exploits_an_opponent_who_never_folds() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..200 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+        let equilibrium = table.average_profile();
+
+        // Player 2 calls every raise instead of folding
+        let table = tree
+            .info_sets
+            .iter()
+            .filter(|info_set| info_set.key.player() == 1 && info_set.legal[2])
+            .map(|info_set| format!("{}=1,0,0", info_set.key))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let model = OpponentModel::from_table(&tree, &table).unwrap();
+
+        assert_eq!(model.exploiting_players(&tree), vec![0]);
+        let exploitation = exploit(&tree, &model, &equilibrium, 0);
+
+        assert!(
+            exploitation.best_response > exploitation.equilibrium + 0.1,
+            "{} {}",
+            exploitation.best_response,
+            exploitation.equilibrium
+        );
+    }
+
+    #[test]
+    fn restricted_nash_response_follows_the_model() {
+        let tree = GameTree::new();
+        let model =
+            OpponentModel::from_table(&tree, "J iir=1,0,0\nQ iir=1,0,0\nK iir=1,0,0").unwrap();
+        let restricted = restricted_tree(&tree, &model, 0.5);
+
+        // Only the three modelled information sets are copied, half of the deals see the model
+        assert_eq!(restricted.info_sets.len(), tree.info_sets.len() + 3);
+        let Node::Chance { outcomes } = &restricted.nodes[restricted.root] else {
+            panic!("the restricted game starts with the deal");
+        };
+        assert_eq!(outcomes.len(), 60);
+        assert!((outcomes.iter().map(|&(pr, _)| pr).sum::<f64>() - 1.0).abs() < 1e-12);
+
+        let mut table = InfoSetTable::new(&restricted.info_sets);
+        lock_model(&mut table, &tree, &model);
+        let mut workers = Workers::serial(&restricted);
+        for _ in 0..100 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
         }
+
+        let profile = &table.average_profile()[..tree.info_sets.len()];
+        let point = evaluate(&tree, &model, profile, 0);
+        assert!(point.against_model >= point.worst_case);
+        assert!(best_response(&tree, &model.apply(profile), 0).value >= point.against_model);
     }
 
-    fn make_positive(&self, x: [f64; N_ACTIONS]) -> [f64; N_ACTIONS] {
-        x.map(|val| val.max(0.0))
+    #[test]
+    fn fictitious_play_alternates_the_responses() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        fictitious_play(&tree, &mut table);
+
+        // Player 2 responds to the average that already holds the response of player 1
+        let profile = table.average_profile();
+        let response = best_response(&tree, &profile, 1);
+        for (i, action) in response.actions.iter().enumerate() {
+            if let Some(action) = *action {
+                if table.reach_pr_sum[i] > 0.0 {
+                    assert_eq!(profile[i][action], 1.0, "{}", i);
+                }
+            }
+        }
     }
-}
 
-impl fmt::Display for InformationSet {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let strategies: Vec<String> = self
-            .get_average_strategy()
+    #[test]
+    fn fictitious_play_lowers_exploitability() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let uniform = exploitability(&tree, &table.average_profile());
+
+        for _ in 0..50 {
+            fictitious_play(&tree, &mut table);
+        }
+
+        let profile = table.average_profile();
+        assert!(exploitability(&tree, &profile) < uniform / 4.0);
+        assert!(profile
             .iter()
-            .map(|x| format!("{:03.2}", x))
-            .collect();
+            .zip(tree.info_sets.iter())
+            .all(|(strategy, info_set)| {
+                strategy
+                    .iter()
+                    .zip(info_set.legal.iter())
+                    .all(|(&x, &is_legal)| is_legal || x == 0.0)
+            }));
+    }
 
-        let mut card = self.key.clone();
-        if card.split_whitespace().last().map_or(0, |s| s.len()) > 3 {
-            card = format!(
-                "{}{}{}",
-                &card[0..1],
-                card_str(*PUBLIC_CARD as usize),
-                &card[1..]
-            );
+    #[test]
+    fn cfr_matches_sequence_form() {
+        let tree = GameTree::new();
+        let (value, profile) = sequence_form::solve(&tree).unwrap();
+
+        // The game is worth about -0.0856 to player 1
+        assert!((value + 0.0856).abs() < 1e-4, "{}", value);
+        assert!((expected_utility(&tree, &profile) - value).abs() < 1e-9);
+        assert!(exploitability(&tree, &profile) < 1e-9);
+
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..1000 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
         }
 
-        write!(f, "{} [{}]", card, strategies.join(", "))
+        let utility = expected_utility(&tree, &table.average_profile());
+        assert!((utility - value).abs() < 0.01, "{} != {}", utility, value);
     }
-}
 
-fn display_results(ev: f64, i_map: &HashMap<String, InformationSet>) {
-    println!();
-    println!("==== notation ====");
-    println!("i => initial");
-    println!("==================");
-    println!();
+    #[test]
+    fn counterfactual_values_explain_the_regrets() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
 
-    println!("player 1 expected value: {}", ev);
-    println!("player 2 expected value: {}", -1.0 * ev);
-    println!();
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..100 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
 
-    println!("******* PUBLIC CARD ********");
-    println!(
-        "-----------> {} <------------",
-        card_str(*PUBLIC_CARD as usize)
-    );
-    println!("****************************");
-    println!();
+        // The regret of an action is its value minus the value of the strategy played,
+        // so the difference of two regrets is the difference of their values
+        for (i, info_set) in tree.info_sets.iter().enumerate() {
+            let regret = &table.regret_sum[i * N_ACTIONS..(i + 1) * N_ACTIONS];
+            let value = &table.action_value_sum[i * N_ACTIONS..(i + 1) * N_ACTIONS];
+            let legal: Vec<usize> = (0..N_ACTIONS).filter(|&a| info_set.legal[a]).collect();
 
-    let mut items = i_map.iter().collect::<Vec<_>>();
+            for &action in &legal[1..] {
+                let first = legal[0];
+                let gap = (regret[action] - regret[first]) - (value[action] - value[first]);
+                assert!(gap.abs() < 1e-9, "{}", info_set.key);
+            }
+        }
+    }
 
-    items.sort_by(|a, b| a.0.cmp(b.0));
+    #[test]
+    fn thread_count_does_not_change_the_tables() {
+        let tree = GameTree::new();
+        let train = |n_threads| {
+            with_workers(&tree, n_threads, |workers| {
+                let mut table = InfoSetTable::new(&tree.info_sets);
+                for _ in 0..50 {
+                    workers.chance_util(&mut table);
+                    table.next_strategy();
+                }
+                (workers.n_threads(), table)
+            })
+        };
 
-    let (p1_items, p2_items) = items
-        .into_iter()
-        .partition::<Vec<_>, _>(|(k, _)| k.len() % 2 == 0);
+        // The blocks of deals are merged in order, so the sums are the same to the bit
+        let ((n_one, one), (n_four, four)) = (train(1), train(4));
+        assert_eq!((n_one, n_four), (1, 4));
+        assert_eq!(one.regret_sum, four.regret_sum);
+        assert_eq!(one.strategy_sum, four.strategy_sum);
+        assert_eq!(one.reach_pr_sum, four.reach_pr_sum);
+    }
 
-    println!("\nplayer 1 strategies:");
-    for (_, v) in p1_items {
-        println!("{}", v);
+    #[test]
+    fn public_cards_wait_for_the_first_round_to_close() {
+        for info_set in &GameTree::new().info_sets {
+            let key = &info_set.key;
+            let state = BettingState::new(&format!("ii{}", key.rounds[0]));
+
+            // Nobody has a raise left to answer once the public card is shown
+            assert_eq!(key.public_cards.len(), state.round, "{}", key);
+            if state.round == 1 {
+                assert_eq!(state.contributions[0], state.contributions[1], "{}", key);
+            }
+        }
     }
 
-    println!("\nplayer 2 strategies:");
-    for (_, v) in p2_items {
-        println!("{}", v);
+    #[test]
+    fn public_cards_come_from_the_rest_of_the_deck() {
+        let tree = GameTree::new();
+        let decision = |node: usize| match &tree.nodes[node] {
+            Node::Decision {
+                info_set, children, ..
+            } => (&tree.info_sets[*info_set].key, *children),
+            _ => panic!("expected a decision at node {}", node),
+        };
+
+        for node in &tree.nodes {
+            if let Node::Chance { outcomes } = node {
+                assert!((outcomes.iter().map(|&(pr, _)| pr).sum::<f64>() - 1.0).abs() < 1e-12);
+            }
+        }
+
+        let Node::Chance { outcomes: deals } = &tree.nodes[tree.root] else {
+            panic!("the game starts with the deal");
+        };
+        for &(_, deal) in deals {
+            // Both players check the first round, their keys tell their ranks
+            let (key_1, children) = decision(deal);
+            let (key_2, children) = decision(children[0].unwrap());
+            let Node::Chance { outcomes } = &tree.nodes[children[0].unwrap()] else {
+                panic!("the public card follows the first round");
+            };
+
+            let mut remaining: Vec<usize> = (0..N_CARDS as isize)
+                .map(|card| card_rank(card) as usize)
+                .collect();
+            for rank in [key_1.card, key_2.card] {
+                let i = remaining.iter().position(|&x| x == rank).unwrap();
+                remaining.remove(i);
+            }
+
+            let mut dealt: Vec<usize> = outcomes
+                .iter()
+                .map(|&(pr, child)| {
+                    assert_eq!(pr, 1.0 / (N_CARDS - 2) as f64);
+                    decision(child).0.public_cards[0]
+                })
+                .collect();
+            dealt.sort();
+            assert_eq!(dealt, remaining);
+        }
+    }
+
+    #[test]
+    fn showdowns_rank_pairs_over_high_cards() {
+        // Cards are numbered rank * N_SUITS + suit, so 0 and 1 are the Jacks
+        let (jack, jack_2, queen, queen_2, king, king_2) = (0, 1, 2, 3, 4, 5);
+
+        // Both players check twice, each of them put the ante in
+        assert_eq!(terminal_util("iicccc", jack, king, jack_2), 1.0);
+        assert_eq!(terminal_util("iicccc", king, jack, jack_2), -1.0);
+        // Without a pair the higher rank wins, here a raise in each round
+        assert_eq!(terminal_util("iircrc", queen, jack, king), 7.0);
+        assert_eq!(terminal_util("iircrc", jack, queen, king), -7.0);
+        // The same rank splits the pot whatever was bet
+        assert_eq!(terminal_util("iicccc", queen, queen_2, king), 0.0);
+        assert_eq!(terminal_util("iircrc", queen_2, queen, jack), 0.0);
+
+        // With one public card two pairs never meet, the higher one would still win
+        assert!(hand_strength(king, king_2) > hand_strength(queen, queen_2));
+        assert!(hand_strength(queen, queen_2) > hand_strength(jack, jack_2));
+        assert!(hand_strength(jack, jack_2) > hand_strength(king, queen));
+    }
+
+    #[test]
+    fn seeded_simulations_replay() {
+        let tree = GameTree::new();
+        let table = InfoSetTable::new(&tree.info_sets);
+        let profile = table.average_profile();
+        let play = |seed| simulate(&tree, &profile, 1000, &mut StdRng::seed_from_u64(seed));
+
+        assert_eq!(play(1), play(1));
+        assert_ne!(play(1), play(2));
+    }
+
+    #[test]
+    fn duplicate_matches_estimate_the_expected_winnings() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..100 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+        let trained = table.average_profile();
+        let uniform = InfoSetTable::new(&tree.info_sets).average_profile();
+
+        // Half of the hands in each seat, the utilities are given for player 1
+        let expected = (expected_utility(&tree, &seat_profile(&tree, &trained, &uniform, 0))
+            - expected_utility(&tree, &seat_profile(&tree, &trained, &uniform, 1)))
+            / 2.0;
+
+        let play = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            duplicate_match(&tree, &trained, &uniform, 10000, &mut rng)
+        };
+        assert_eq!(play(1), play(1));
+
+        let estimate = Estimate::new(&play(0));
+        assert!(expected > 0.0);
+        assert!(
+            (estimate.mean - expected).abs() < 4.0 * estimate.std_error,
+            "{:?} {}",
+            estimate,
+            expected
+        );
+    }
+
+    #[test]
+    fn trained_strategy_beats_every_baseline() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..500 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+        let trained = table.average_profile();
+
+        for baseline in BASELINES {
+            let profile = baseline.profile(&tree);
+            // The utilities are given for player 1
+            let winnings = (expected_utility(&tree, &seat_profile(&tree, &trained, &profile, 0))
+                - expected_utility(&tree, &seat_profile(&tree, &trained, &profile, 1)))
+                / 2.0;
+
+            assert!(winnings > 0.01, "{}: {}", baseline, winnings);
+            assert_eq!(baseline.to_string().parse(), Ok(baseline));
+            assert!(profile
+                .iter()
+                .zip(tree.info_sets.iter())
+                .all(|(strategy, info_set)| strategy
+                    .iter()
+                    .zip(info_set.legal.iter())
+                    .all(|(&pr, &is_legal)| is_legal || pr == 0.0)));
+        }
+    }
+
+    #[test]
+    fn tournament_ranks_the_trained_strategy_first() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..100 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+        let always_call = baseline::Baseline::AlwaysCall.profile(&tree);
+        let uniform = InfoSetTable::new(&tree.info_sets).average_profile();
+        let entries = vec![
+            ("always_call".to_string(), always_call),
+            ("trained".to_string(), table.average_profile()),
+            ("uniform".to_string(), uniform),
+        ];
+        let tournament = round_robin(&tree, &entries, 2000, 0);
+
+        assert_eq!(tournament.standings()[0], 1);
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(tournament.winnings[i][j], -tournament.winnings[j][i]);
+                assert!((tournament.scores[i][j] + tournament.scores[j][i] - 1.0).abs() < 1e-12);
+                assert!(
+                    (tournament.win_rates[i][j] + tournament.win_rates[j][i] - 1.0).abs() < 1e-12
+                );
+            }
+        }
+
+        // The matches are scored on the winnings, so no strategy is ranked above one
+        // it loses money to
+        let standings = tournament.standings();
+        for (rank, &i) in standings.iter().enumerate() {
+            for &j in &standings[rank + 1..] {
+                assert!(tournament.winnings[i][j] > 0.0, "{:?}", tournament.winnings);
+                assert_eq!(tournament.scores[i][j], 1.0);
+            }
+        }
+
+        // A header and a row per strategy, plus the separator in Markdown
+        assert_eq!(tournament.ratings_csv().lines().count(), 4);
+        assert_eq!(tournament.winnings_markdown().lines().count(), 5);
+        assert_eq!(tournament.win_rates_markdown().lines().count(), 5);
+        assert!(tournament.winnings_csv().starts_with("strategy,trained,"));
+        assert!(tournament
+            .ratings_csv()
+            .starts_with("rank,strategy,rating,score,"));
+    }
+
+    #[test]
+    fn saved_strategies_load_back() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..10 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+        let profile = table.average_profile();
+        let path = std::env::temp_dir().join(format!("leduc-{}.txt", process::id()));
+        let path = path.to_str().unwrap();
+
+        strategy_file::save(&tree, &profile, path).unwrap();
+        let loaded = strategy_file::load(&tree, path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        for (a, b) in profile.iter().zip(loaded.iter()) {
+            assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    fn capped_nodes_never_raise() {
+        let tree = GameTree::new();
+        let mut table = InfoSetTable::new(&tree.info_sets);
+        let raise = CHANCE_ACTIONS.iter().position(|&a| a == "r").unwrap();
+
+        let mut workers = Workers::serial(&tree);
+        for _ in 0..1000 {
+            workers.chance_util(&mut table);
+            table.next_strategy();
+        }
+
+        let capped: Vec<usize> = tree
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                Node::Decision {
+                    info_set, children, ..
+                } if children[raise].is_none() => Some(*info_set),
+                _ => None,
+            })
+            .collect();
+        assert!(!capped.is_empty());
+
+        for info_set in capped {
+            assert!(!tree.info_sets[info_set].legal[raise]);
+            assert_eq!(table.strategy(info_set)[raise], 0.0);
+            assert_eq!(table.get_average_strategy(info_set)[raise], 0.0);
+            assert!((table.strategy(info_set).iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
     }
 }
@@ -0,0 +1,228 @@
+use std::io::{self, BufRead, Write};
+
+use common::simulate::sample;
+use rand::Rng;
+
+use crate::betting::BettingState;
+use crate::tree::{GameTree, Node};
+use crate::{CHANCE_ACTIONS, N_ACTIONS, RANKS};
+
+// A human against the average strategy in the terminal, e.g. `cargo run -p task3 -- --play`
+//
+// The human takes the two seats in turn while the bot samples its actions from
+// `profile`. Hands are dealt until the human answers `q` or the input ends, the
+// total winnings of the human are returned
+pub fn play<R: Rng, I: BufRead, O: Write>(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    rng: &mut R,
+    input: &mut I,
+    output: &mut O,
+) -> io::Result<f64> {
+    let mut score = 0.0;
+
+    for hand in 0.. {
+        let seat = hand % 2;
+        writeln!(output, "\nhand {}, you are player {}", hand + 1, seat + 1)?;
+
+        let Some(winnings) = play_hand(tree, profile, seat, rng, input, output)? else {
+            break;
+        };
+
+        score += winnings;
+        writeln!(output, "score after {} hands: {:+}", hand + 1, score)?;
+    }
+
+    Ok(score)
+}
+
+// Winnings of the human in one hand, `None` when they quit
+fn play_hand<R: Rng, I: BufRead, O: Write>(
+    tree: &GameTree,
+    profile: &[[f64; N_ACTIONS]],
+    seat: usize,
+    rng: &mut R,
+    input: &mut I,
+    output: &mut O,
+) -> io::Result<Option<f64>> {
+    let mut node = tree.root;
+    let mut history = String::from("ii");
+    // The bot's card as the key of its last information set tells it
+    let mut bot_card = "";
+
+    loop {
+        node = match &tree.nodes[node] {
+            Node::Terminal { utility } => {
+                if BettingState::new(&history).folded.is_none() {
+                    writeln!(output, "player {} shows {}", 2 - seat, bot_card)?;
+                }
+
+                // The utility is given for player 1
+                let winnings = if seat == 0 { *utility } else { -utility };
+                if winnings > 0.0 {
+                    writeln!(output, "you win {}", winnings)?;
+                } else if winnings == 0.0 {
+                    writeln!(output, "the pot is split")?;
+                } else {
+                    writeln!(output, "you lose {}", -winnings)?;
+                }
+                return Ok(Some(winnings));
+            }
+            Node::Chance { outcomes } => {
+                let weights: Vec<f64> = outcomes.iter().map(|&(pr, _)| pr).collect();
+                outcomes[sample(&weights, rng)].1
+            }
+            Node::Decision {
+                player,
+                info_set,
+                children,
+            } => {
+                let key = &tree.info_sets[*info_set].key;
+                let legal = tree.info_sets[*info_set].legal;
+
+                let facing_raise = BettingState::new(&history).facing_raise();
+
+                let action = if *player == seat {
+                    let board = key.public_cards.first().map_or("none", |&card| RANKS[card]);
+                    writeln!(
+                        output,
+                        "your card {}, board {}, actions so far '{}'",
+                        RANKS[key.card],
+                        board,
+                        key.rounds.join("/")
+                    )?;
+
+                    match ask(legal, facing_raise, input, output)? {
+                        Some(action) => action,
+                        None => return Ok(None),
+                    }
+                } else {
+                    bot_card = RANKS[key.card];
+
+                    let action = sample(&profile[*info_set], rng);
+                    writeln!(
+                        output,
+                        "player {} {}",
+                        player + 1,
+                        describe(action, facing_raise)
+                    )?;
+                    action
+                };
+
+                history.push_str(CHANCE_ACTIONS[action]);
+                children[action].expect("only legal actions are picked")
+            }
+        };
+    }
+}
+
+// Prompt until the human picks a legal action, `None` when they quit
+fn ask<I: BufRead, O: Write>(
+    legal: [bool; N_ACTIONS],
+    facing_raise: bool,
+    input: &mut I,
+    output: &mut O,
+) -> io::Result<Option<usize>> {
+    let choices = (0..N_ACTIONS)
+        .filter(|&action| legal[action])
+        .map(|action| format!("{} {}", CHANCE_ACTIONS[action], verb(action, facing_raise)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    loop {
+        write!(output, "{} or q to quit: ", choices)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim() == "q" {
+            return Ok(None);
+        }
+
+        let action = CHANCE_ACTIONS
+            .iter()
+            .position(|&name| name == line.trim())
+            .filter(|&action| legal[action]);
+        if action.is_some() {
+            return Ok(action);
+        }
+        writeln!(output, "choose one of {}", choices)?;
+    }
+}
+
+// 'c' checks when there is nothing to call
+fn verb(action: usize, facing_raise: bool) -> &'static str {
+    match (action, facing_raise) {
+        (0, false) => "check",
+        (0, true) => "call",
+        (1, _) => "raise",
+        _ => "fold",
+    }
+}
+
+fn describe(action: usize, facing_raise: bool) -> &'static str {
+    match (action, facing_raise) {
+        (0, false) => "checks",
+        (0, true) => "calls",
+        (1, _) => "raises",
+        _ => "folds",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::minimizer::uniform_strategy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn session(input: &str, seed: u64) -> (f64, String) {
+        let tree = GameTree::new();
+        let profile: Vec<[f64; N_ACTIONS]> = tree
+            .info_sets
+            .iter()
+            .map(|info_set| uniform_strategy(&info_set.legal))
+            .collect();
+        let mut output = Vec::new();
+
+        let score = play(
+            &tree,
+            &profile,
+            &mut StdRng::seed_from_u64(seed),
+            &mut input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        (score, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn hands_are_played_until_the_human_quits() {
+        let input = "x
+c
+c
+c
+c
+c
+c
+c
+c
+q
+";
+        let (score, output) = session(input, 5);
+
+        // Folding is only offered against a raise
+        assert!(output.contains("choose one of c check, r raise\n"));
+        assert!(output.contains("hand 2, you are player 2"));
+        assert!(output.contains("score after 1 hands"));
+        assert_eq!(session(input, 5), (score, output));
+    }
+
+    #[test]
+    fn split_pots_are_not_losses() {
+        // The third hand of this seed is a showdown between two Kings
+        let (_, output) = session("c\nc\nc\nc\nc\nc\nc\nc\nq\n", 6);
+
+        assert!(output.contains("the pot is split"));
+        assert!(!output.contains("you lose 0"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use common::agent::InfoSets;
+use common::simulate::{Game, Move};
+
+use crate::betting::BettingState;
+use crate::info_set::InformationSet;
+use crate::key::InfoSetKey;
+use crate::{
+    is_chance_node, is_terminal, legal_actions, terminal_util, CHANCE_ACTIONS, N_ACTIONS, N_CARDS,
+};
+
+// A node of the game tree, children are indices into `GameTree::nodes`
+#[derive(Debug)]
+pub enum Node {
+    // Random moves with their probabilities
+    Chance {
+        outcomes: Vec<(f64, usize)>,
+    },
+    // End of the game, the utility is given for player 1
+    Terminal {
+        utility: f64,
+    },
+    // A player picks one of `CHANCE_ACTIONS`, `None` when the action is not legal
+    Decision {
+        player: usize,
+        info_set: usize,
+        children: [Option<usize>; N_ACTIONS],
+    },
+}
+
+// The whole game, built once so that the CFR iterations only follow indices
+#[derive(Debug)]
+pub struct GameTree {
+    pub nodes: Vec<Node>,
+    pub info_sets: Vec<InformationSet>,
+    pub root: usize,
+}
+
+impl GameTree {
+    pub fn new() -> GameTree {
+        let mut tree = GameTree {
+            nodes: Vec::new(),
+            info_sets: Vec::new(),
+            root: 0,
+        };
+        let mut indices = HashMap::new();
+
+        let deals: Vec<(isize, isize)> = (0..N_CARDS)
+            .flat_map(|i| (0..N_CARDS).map(move |j| (i, j)))
+            .filter(|(i, j)| i != j)
+            .map(|(i, j)| (i as isize, j as isize))
+            .collect();
+        let n_possibilities = deals.len();
+
+        let outcomes = deals
+            .into_iter()
+            .map(|(card_1, card_2)| {
+                let child = tree.build(&mut indices, "ii", card_1, card_2, -1);
+                (1.0 / n_possibilities as f64, child)
+            })
+            .collect();
+
+        tree.root = tree.push(Node::Chance { outcomes });
+        tree
+    }
+
+    fn build(
+        &mut self,
+        indices: &mut HashMap<InfoSetKey, usize>,
+        history: &str,
+        card_1: isize,
+        card_2: isize,
+        public_card: isize,
+    ) -> usize {
+        if is_terminal(history) {
+            return self.push(Node::Terminal {
+                utility: terminal_util(history, card_1, card_2, public_card),
+            });
+        }
+
+        if is_chance_node(history, public_card) {
+            // The public card is drawn from the cards left in the deck
+            let remaining: Vec<isize> = (0..N_CARDS as isize)
+                .filter(|&card| card != card_1 && card != card_2)
+                .collect();
+
+            let outcomes = remaining
+                .iter()
+                .map(|&card| {
+                    let child = self.build(indices, history, card_1, card_2, card);
+                    (1.0 / remaining.len() as f64, child)
+                })
+                .collect();
+
+            return self.push(Node::Chance { outcomes });
+        }
+
+        let player = BettingState::new(history).player;
+        let key = InfoSetKey::new(
+            if player == 0 { card_1 } else { card_2 },
+            public_card,
+            history,
+        );
+        let legal = legal_actions(history);
+
+        let info_sets = &mut self.info_sets;
+        let info_set = *indices.entry(key).or_insert_with_key(|key| {
+            info_sets.push(InformationSet::new(key, legal));
+            info_sets.len() - 1
+        });
+
+        let mut children = [None; N_ACTIONS];
+        for (i, action) in CHANCE_ACTIONS.iter().enumerate() {
+            if legal[i] {
+                children[i] = Some(self.build(
+                    indices,
+                    &format!("{}{}", history, action),
+                    card_1,
+                    card_2,
+                    public_card,
+                ));
+            }
+        }
+
+        self.push(Node::Decision {
+            player,
+            info_set,
+            children,
+        })
+    }
+
+    fn push(&mut self, node: Node) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+}
+
+impl InfoSets for GameTree {
+    fn n_info_sets(&self) -> usize {
+        self.info_sets.len()
+    }
+}
+
+impl Game<N_ACTIONS> for GameTree {
+    fn root(&self) -> usize {
+        self.root
+    }
+
+    fn n_players(&self) -> usize {
+        2
+    }
+
+    fn player(&self, info_set: usize) -> usize {
+        self.info_sets[info_set].key.player()
+    }
+
+    fn node(&self, node: usize) -> Move<'_, N_ACTIONS> {
+        match &self.nodes[node] {
+            Node::Chance { outcomes } => Move::Chance(outcomes),
+            Node::Terminal { .. } => Move::Terminal,
+            Node::Decision {
+                info_set, children, ..
+            } => Move::Decision {
+                info_set: *info_set,
+                children: *children,
+            },
+        }
+    }
+
+    // The utility of player 2 is the opposite of the one of player 1
+    fn winnings(&self, node: usize, player: usize) -> f64 {
+        let Node::Terminal { utility } = self.nodes[node] else {
+            unreachable!("only terminal nodes pay out");
+        };
+        if player == 0 {
+            utility
+        } else {
+            -utility
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::mem;
+use std::ops::Range;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, Scope};
+
+use crate::cfr;
+use crate::info_set::{InfoSetTable, RegretBuffer};
+use crate::tree::{GameTree, Node};
+
+// Nodes below the deals of a block, smaller blocks would cost more to hand to a
+// thread and to merge than their traversal takes
+const MIN_NODES_PER_BLOCK: usize = 2000;
+
+// CFR iterations with the deals split between threads that are kept from one
+// iteration to the next, e.g. `--threads 4`
+//
+// The deals are cut into blocks from the size of the tree alone and every block adds
+// up its deals in its own buffer. A thread traverses the same blocks in every
+// iteration and the calling thread takes the first ones. The blocks are merged in
+// order, so the tables do not depend on the number of threads. Trees too small for
+// two blocks are traversed on the calling thread
+pub struct Workers<'a> {
+    tree: &'a GameTree,
+    deals: &'a [(f64, usize)],
+    // The blocks of the calling thread first, then the ones of every other thread
+    blocks: Vec<Vec<Block>>,
+    threads: Vec<WorkerThread>,
+}
+
+// A run of deals with the updates and the expected value they add up to
+#[derive(Debug)]
+struct Block {
+    deals: Range<usize>,
+    buffer: RegretBuffer,
+    value: f64,
+}
+
+struct WorkerThread {
+    jobs: Sender<(Arc<InfoSetTable>, Vec<Block>)>,
+    results: Receiver<Vec<Block>>,
+}
+
+impl<'a> Workers<'a> {
+    // Up to `n_threads` threads that live as long as `scope`, never more than there
+    // are blocks
+    pub fn new<'env>(
+        scope: &'a Scope<'a, 'env>,
+        tree: &'env GameTree,
+        n_threads: usize,
+    ) -> Workers<'a> {
+        let mut workers = Workers::serial(tree);
+        let mut blocks = workers.blocks.remove(0);
+        let n_blocks = blocks.len();
+        let n_threads = n_threads.clamp(1, n_blocks);
+        let deals = workers.deals;
+
+        // Thread `t` takes the blocks from `t * n_blocks / n_threads` on, they are split
+        // off the end starting with the last thread
+        for t in (0..n_threads).rev() {
+            let own = blocks.split_off(t * n_blocks / n_threads);
+            workers.blocks.insert(0, own);
+        }
+
+        for _ in 1..n_threads {
+            let (jobs, job_receiver) = mpsc::channel::<(Arc<InfoSetTable>, Vec<Block>)>();
+            let (result_sender, results) = mpsc::channel();
+
+            scope.spawn(move || {
+                for (table, mut blocks) in job_receiver {
+                    traverse(tree, &table, deals, &mut blocks);
+                    // The caller takes the table back once every thread is done with it
+                    drop(table);
+                    if result_sender.send(blocks).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            workers.threads.push(WorkerThread { jobs, results });
+        }
+
+        workers
+    }
+
+    // Every block on the calling thread
+    pub fn serial(tree: &'a GameTree) -> Workers<'a> {
+        let Node::Chance { outcomes } = &tree.nodes[tree.root] else {
+            unreachable!("the game starts with the deal");
+        };
+        let n_blocks = (tree.nodes.len() / MIN_NODES_PER_BLOCK).clamp(1, outcomes.len());
+
+        let blocks = (0..n_blocks)
+            .map(|b| Block {
+                deals: b * outcomes.len() / n_blocks..(b + 1) * outcomes.len() / n_blocks,
+                buffer: RegretBuffer::new(tree.info_sets.len()),
+                value: 0.0,
+            })
+            .collect();
+
+        Workers {
+            tree,
+            deals: outcomes,
+            blocks: vec![blocks],
+            threads: Vec::new(),
+        }
+    }
+
+    pub fn n_threads(&self) -> usize {
+        self.threads.len() + 1
+    }
+
+    // One CFR iteration over every deal, returns the expected value of player 1
+    pub fn chance_util(&mut self, table: &mut InfoSetTable) -> f64 {
+        let shared = Arc::new(mem::replace(table, InfoSetTable::new(&[])));
+
+        for (thread, blocks) in self.threads.iter().zip(&mut self.blocks[1..]) {
+            thread
+                .jobs
+                .send((Arc::clone(&shared), mem::take(blocks)))
+                .expect("the worker threads live as long as the workers");
+        }
+
+        traverse(self.tree, &shared, self.deals, &mut self.blocks[0]);
+        for (thread, blocks) in self.threads.iter().zip(&mut self.blocks[1..]) {
+            *blocks = thread.results.recv().expect("a worker thread panicked");
+        }
+
+        *table = Arc::try_unwrap(shared).expect("the worker threads dropped the table");
+
+        let mut expected_value = 0.0;
+        for block in self.blocks.iter_mut().flatten() {
+            expected_value += block.value;
+            table.merge(&block.buffer);
+            block.buffer.clear();
+        }
+        expected_value
+    }
+}
+
+// Run `f` with `n_threads` workers for `tree`, the threads are joined when it returns
+pub fn with_workers<R>(tree: &GameTree, n_threads: usize, f: impl FnOnce(&mut Workers) -> R) -> R {
+    thread::scope(|scope| f(&mut Workers::new(scope, tree, n_threads)))
+}
+
+// Traverse the deals of every block, the values are weighted by the probabilities of
+// the deals
+fn traverse(tree: &GameTree, table: &InfoSetTable, deals: &[(f64, usize)], blocks: &mut [Block]) {
+    for block in blocks {
+        block.value = 0.0;
+        for &(pr, child) in &deals[block.deals.clone()] {
+            block.value += pr * cfr(tree, table, &mut block.buffer, child, 1.0, 1.0, pr);
+        }
+    }
+}